@@ -0,0 +1,847 @@
+//! Project configuration, read from the `[tool.chapter]` table of
+//! `pyproject.toml` or from a standalone `chapter.toml`.
+//!
+//! ```toml
+//! [tool.chapter]
+//! source-roots = ["src"]
+//! exclude = ["**/tests/**"]
+//! docstring-style = "google"
+//! private-members = "exclude"
+//! output-dir = "docs/api"
+//! output-format = "markdown"
+//!
+//! [tool.chapter.coverage]
+//! fail-under = 80
+//!
+//! [[tool.chapter.overrides]]
+//! modules = "pkg._vendor.*"
+//! skip = true
+//! ```
+use std::cell::RefCell;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{fs, io};
+
+use regex::Regex;
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+use crate::context_engine::rules::{DocstringStyle, LogicContext};
+use crate::package::{Package, Renderer};
+
+/// Standalone configuration file, preferred over `pyproject.toml`
+pub const CONFIG_FILE: &str = "chapter.toml";
+pub const PYPROJECT_FILE: &str = "pyproject.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivateMembers {
+    Include,
+    Exclude,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Markdown,
+
+    /// Needs user templates, the built-in ones being Markdown
+    Html,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Minimum percentages of documented public modules, classes, functions,
+/// methods and properties.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Coverage {
+    /// For the whole package
+    pub fail_under: Option<f64>,
+
+    /// For every module
+    pub module_fail_under: Option<f64>,
+}
+
+/// Module or whole package documenting less than required.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageFailure {
+    /// Name of the module, `None` for the whole package
+    pub module: Option<String>,
+
+    /// Percentage of documented symbols
+    pub coverage: f64,
+    pub fail_under: f64,
+}
+
+impl fmt::Display for CoverageFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.1}% of the public symbols are documented, expected at least {}%",
+            self.module.as_deref().unwrap_or("package"),
+            self.coverage,
+            self.fail_under
+        )
+    }
+}
+
+/// Glob compiled to an anchored expression: `**` matches across separators,
+/// `*` and `?` within a single part.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    glob: String,
+    regex: Regex,
+}
+
+impl Glob {
+    pub fn new(glob: &str, separator: char) -> Self {
+        Self {
+            glob: glob.to_string(),
+            regex: glob_regex(glob, separator),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.glob
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+}
+
+impl PartialEq for Glob {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+/// Settings of the modules matching a pattern such as `pkg.legacy.*`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleOverride {
+    pub modules: Vec<Glob>,
+
+    /// Leaves the modules out of the package
+    pub skip: bool,
+    pub docstring_style: Option<DocstringStyle>,
+    pub private_members: Option<PrivateMembers>,
+    pub fail_under: Option<f64>,
+}
+
+impl ModuleOverride {
+    pub fn matches(&self, module: &str) -> bool {
+        self.modules.iter().any(|glob| glob.is_match(module))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Directory of the configuration file, against which relative paths
+    /// are resolved
+    pub root: PathBuf,
+
+    /// Directories holding the top level packages
+    pub source_roots: Vec<PathBuf>,
+
+    /// Globs of the source files to read, relative to the configuration
+    /// directory
+    pub include: Vec<Glob>,
+    pub exclude: Vec<Glob>,
+    pub docstring_style: DocstringStyle,
+    pub private_members: PrivateMembers,
+    pub output_dir: PathBuf,
+    pub output_format: OutputFormat,
+
+    /// Directory of user templates replacing the built-in ones
+    pub templates: Option<PathBuf>,
+    pub coverage: Coverage,
+
+    /// Per module settings, later entries taking precedence
+    pub overrides: Vec<ModuleOverride>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            source_roots: vec![PathBuf::from(".")],
+            include: vec![Glob::new("**/*.py", '/'), Glob::new("**/*.pyi", '/')],
+            exclude: Vec::new(),
+            docstring_style: DocstringStyle::Auto,
+            private_members: PrivateMembers::Exclude,
+            output_dir: PathBuf::from("docs/api"),
+            output_format: OutputFormat::Markdown,
+            templates: None,
+            coverage: Coverage::default(),
+            overrides: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+
+    /// The file is not valid TOML
+    Syntax {
+        file: PathBuf,
+        line: Option<usize>,
+        message: String,
+    },
+
+    /// Unknown key or value of the wrong type
+    Key {
+        file: PathBuf,
+
+        /// Dotted path of the key, e.g. `tool.chapter.output-format`
+        key: String,
+
+        /// Zero based line of the key or of its value
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "cannot read the configuration: {}", error),
+            Self::Syntax { file, line: Some(line), message } => {
+                write!(f, "{}:{}: {}", file.display(), line + 1, message)
+            }
+            Self::Syntax { file, line: None, message } => write!(f, "{}: {}", file.display(), message),
+            Self::Key { file, key, line, message } => {
+                write!(f, "{}:{}: `{}`: {}", file.display(), line + 1, key, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl Config {
+    /// Finds the configuration of a directory or of its closest parent: a
+    /// `chapter.toml` file, or a `pyproject.toml` file with a
+    /// `[tool.chapter]` table.
+    pub fn discover(path: &Path) -> Result<Option<Self>, ConfigError> {
+        for directory in path.ancestors() {
+            for name in [CONFIG_FILE, PYPROJECT_FILE] {
+                let file = directory.join(name);
+                if !file.is_file() {
+                    continue;
+                }
+                if let Some(config) = Self::parse_document(&fs::read_to_string(&file)?, &file)? {
+                    return Ok(Some(config));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn read(file: &Path) -> Result<Self, ConfigError> {
+        Self::parse(&fs::read_to_string(file)?, file)
+    }
+
+    /// Parses the text of a configuration file. Settings are read from the
+    /// `[tool.chapter]` table of `pyproject.toml` files and from the top
+    /// level of other files.
+    pub fn parse(source: &str, file: &Path) -> Result<Self, ConfigError> {
+        let root = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Ok(Self::parse_document(source, file)?.unwrap_or(Self {
+            root,
+            ..Self::default()
+        }))
+    }
+
+    fn parse_document(source: &str, file: &Path) -> Result<Option<Self>, ConfigError> {
+        let document = DeTable::parse(source).map_err(|e| ConfigError::Syntax {
+            file: file.to_path_buf(),
+            line: e.span().map(|span| line_of(source, span.start)),
+            message: e.message().to_string(),
+        })?;
+        let reader = Reader { file, source };
+        let mut config = Self {
+            root: file.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            ..Self::default()
+        };
+
+        let is_pyproject = file.file_name().is_some_and(|n| n == PYPROJECT_FILE);
+        let table = match is_pyproject {
+            true => {
+                let tool = match get(document.get_ref(), "tool") {
+                    Some(tool) => reader.table("tool", tool)?,
+                    None => return Ok(None),
+                };
+                match get(tool, "chapter") {
+                    Some(chapter) => reader.table("tool.chapter", chapter)?,
+                    None => return Ok(None),
+                }
+            }
+            false => document.get_ref(),
+        };
+        let prefix = if is_pyproject { "tool.chapter." } else { "" };
+
+        for (key, value) in table.iter() {
+            let name = key.get_ref().as_ref();
+            let path = format!("{}{}", prefix, name);
+            match name {
+                "source-roots" => {
+                    config.source_roots = reader.strings(&path, value)?.into_iter().map(PathBuf::from).collect()
+                }
+                "include" => config.include = reader.globs(&path, value, '/')?,
+                "exclude" => config.exclude = reader.globs(&path, value, '/')?,
+                "docstring-style" => config.docstring_style = reader.docstring_style(&path, value)?,
+                "private-members" => config.private_members = reader.private_members(&path, value)?,
+                "output-dir" => config.output_dir = PathBuf::from(reader.string(&path, value)?),
+                "output-format" => {
+                    config.output_format = reader.choice(
+                        &path,
+                        value,
+                        &[("markdown", OutputFormat::Markdown), ("html", OutputFormat::Html)],
+                    )?
+                }
+                "templates" => config.templates = Some(PathBuf::from(reader.string(&path, value)?)),
+                "coverage" => {
+                    for (key, value) in reader.table(&path, value)?.iter() {
+                        let name = key.get_ref().as_ref();
+                        let path = format!("{}.{}", path, name);
+                        match name {
+                            "fail-under" => config.coverage.fail_under = Some(reader.percentage(&path, value)?),
+                            "module-fail-under" => {
+                                config.coverage.module_fail_under = Some(reader.percentage(&path, value)?)
+                            }
+                            _ => return Err(reader.unknown_key(&path, key)),
+                        }
+                    }
+                }
+                "overrides" => {
+                    let entries = match value.get_ref() {
+                        DeValue::Array(entries) => entries,
+                        _ => return Err(reader.error(&path, value.span(), "expected an array of tables")),
+                    };
+                    for (index, entry) in entries.iter().enumerate() {
+                        let path = format!("{}[{}]", path, index);
+                        config.overrides.push(reader.module_override(&path, entry)?);
+                    }
+                }
+                _ => return Err(reader.unknown_key(&path, key)),
+            }
+        }
+        Ok(Some(config))
+    }
+
+    fn overrides<'a>(&'a self, module: &'a str) -> impl Iterator<Item = &'a ModuleOverride> + 'a {
+        self.overrides.iter().rev().filter(move |o| o.matches(module))
+    }
+
+    pub fn is_skipped(&self, module: &str) -> bool {
+        self.overrides(module).any(|o| o.skip)
+    }
+
+    pub fn docstring_style(&self, module: &str) -> DocstringStyle {
+        self.overrides(module)
+            .find_map(|o| o.docstring_style)
+            .unwrap_or(self.docstring_style)
+    }
+
+    pub fn private_members(&self, module: &str) -> PrivateMembers {
+        self.overrides(module)
+            .find_map(|o| o.private_members)
+            .unwrap_or(self.private_members)
+    }
+
+    /// Returns the coverage a module must reach, if any.
+    pub fn fail_under(&self, module: &str) -> Option<f64> {
+        self.overrides(module)
+            .find_map(|o| o.fail_under)
+            .or(self.coverage.module_fail_under)
+    }
+
+    /// Returns the parsing rules of a module.
+    pub fn rules(&self, module: &str) -> LogicContext {
+        LogicContext::new()
+            .keep_private(self.private_members(module) == PrivateMembers::Include)
+            .docstring_style(self.docstring_style(module))
+    }
+
+    /// Tells whether a file, given relative to the configuration directory,
+    /// is selected by the include and exclude globs.
+    pub fn is_included(&self, relative: &str) -> bool {
+        self.include.iter().any(|g| g.is_match(relative)) && !self.exclude.iter().any(|g| g.is_match(relative))
+    }
+
+    /// Reads the selected sources of every source root, each parsed with the
+    /// rules of its module.
+    pub fn load_package(&self) -> Result<Package, ConfigError> {
+        let mut package = Package::new();
+        for source_root in self.source_roots.iter() {
+            let source_root = self.root.join(source_root);
+            let mut pending = vec![(source_root.clone(), Vec::new())];
+            while let Some((directory, prefix)) = pending.pop() {
+                let mut entries = fs::read_dir(&directory)?
+                    .map(|entry| entry.map(|e| e.path()))
+                    .collect::<io::Result<Vec<_>>>()?;
+                entries.sort();
+                for entry in entries {
+                    let stem = match entry.file_stem().and_then(|s| s.to_str()) {
+                        Some(stem) => stem.to_string(),
+                        None => continue,
+                    };
+                    let mut parts: Vec<String> = prefix.clone();
+                    if entry.is_dir() {
+                        parts.push(stem);
+                        pending.push((entry, parts));
+                        continue;
+                    }
+                    let relative = entry
+                        .strip_prefix(&self.root)
+                        .unwrap_or(&entry)
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    let is_source = entry.extension().is_some_and(|e| e == "py" || e == "pyi");
+                    if !is_source || !self.is_included(&relative) {
+                        continue;
+                    }
+                    if stem != "__init__" {
+                        parts.push(stem);
+                    }
+                    let name = parts.join(".");
+                    if !self.is_skipped(&name) {
+                        package.add_file(&entry, &name, self.rules(&name))?;
+                    }
+                }
+            }
+        }
+        Ok(package)
+    }
+
+    /// Returns the renderer of the output format, with the user templates
+    /// and the private member policy of each module of the package.
+    pub fn renderer(&self, package: &Package) -> io::Result<Renderer> {
+        let mut renderer = Renderer::new()
+            .extension(self.output_format.extension())
+            .include_private(self.private_members == PrivateMembers::Include);
+        for name in package.modules.keys() {
+            renderer = renderer.include_private_in(name, self.private_members(name) == PrivateMembers::Include);
+        }
+        match &self.templates {
+            Some(templates) => renderer.template_dir(&self.root.join(templates)),
+            None => Ok(renderer),
+        }
+    }
+
+    /// Returns the output directory, resolved against the configuration
+    /// directory.
+    pub fn output_path(&self) -> PathBuf {
+        self.root.join(&self.output_dir)
+    }
+
+    /// Checks the share of documented public symbols of every module and of
+    /// the whole package against the coverage thresholds.
+    pub fn check_coverage(&self, package: &Package) -> Vec<CoverageFailure> {
+        let mut failures = Vec::new();
+        let (mut documented, mut total) = (0, 0);
+        for module in package.modules.values() {
+            let mut counts = (0, 0);
+            count_documented(&module.root, &mut counts);
+            documented += counts.0;
+            total += counts.1;
+            if let Some(fail_under) = self.fail_under(&module.name) {
+                let coverage = percentage(counts.0, counts.1);
+                if coverage < fail_under {
+                    failures.push(CoverageFailure {
+                        module: Some(module.name.clone()),
+                        coverage,
+                        fail_under,
+                    });
+                }
+            }
+        }
+        if let Some(fail_under) = self.coverage.fail_under {
+            let coverage = percentage(documented, total);
+            if coverage < fail_under {
+                failures.push(CoverageFailure {
+                    module: None,
+                    coverage,
+                    fail_under,
+                });
+            }
+        }
+        failures
+    }
+}
+
+/// Counts the documented and the total public symbols of a tree: the module,
+/// classes, functions, methods and properties. Dunder methods are left out,
+/// `__init__` being documented by its class.
+fn count_documented(node: &Rc<RefCell<ContextNode>>, counts: &mut (usize, usize)) {
+    let node = node.borrow();
+    let counted = match node.context_type {
+        ContextType::ROOT => true,
+        ContextType::CLASS | ContextType::METHOD | ContextType::PROPERTY => {
+            node.is_public && !node.identifier().starts_with("__")
+        }
+        _ => false,
+    };
+    if counted {
+        counts.1 += 1;
+        if node.docstring().is_some() {
+            counts.0 += 1;
+        }
+    }
+    if matches!(node.context_type, ContextType::ROOT | ContextType::CLASS) {
+        for child in node.children.iter() {
+            count_documented(child, counts);
+        }
+    }
+}
+
+fn percentage(part: usize, total: usize) -> f64 {
+    match total {
+        0 => 100.0,
+        _ => part as f64 * 100.0 / total as f64,
+    }
+}
+
+fn get<'a, 'i>(table: &'a DeTable<'i>, key: &str) -> Option<&'a Spanned<DeValue<'i>>> {
+    table
+        .iter()
+        .find(|(k, _)| k.get_ref().as_ref() == key)
+        .map(|(_, v)| v)
+}
+
+fn line_of(source: &str, offset: usize) -> usize {
+    source
+        .get(..offset)
+        .unwrap_or(source)
+        .matches('\n')
+        .count()
+}
+
+fn glob_regex(glob: &str, separator: char) -> Regex {
+    let part = format!("[^{}]", regex::escape(&separator.to_string()));
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&separator) {
+                    chars.next();
+                    pattern.push_str(&format!("(?:.*{})?", regex::escape(&separator.to_string())));
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str(&format!("{}*", part)),
+            '?' => pattern.push_str(&part),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).unwrap()
+}
+
+/// Reads the values of a configuration file, reporting the offending key
+/// and line on error.
+struct Reader<'a> {
+    file: &'a Path,
+    source: &'a str,
+}
+
+impl Reader<'_> {
+    fn error(&self, key: &str, span: Range<usize>, message: &str) -> ConfigError {
+        ConfigError::Key {
+            file: self.file.to_path_buf(),
+            key: key.to_string(),
+            line: line_of(self.source, span.start),
+            message: message.to_string(),
+        }
+    }
+
+    fn unknown_key<T>(&self, key: &str, name: &Spanned<T>) -> ConfigError {
+        self.error(key, name.span(), "unknown key")
+    }
+
+    fn table<'a, 'i>(&self, key: &str, value: &'a Spanned<DeValue<'i>>) -> Result<&'a DeTable<'i>, ConfigError> {
+        match value.get_ref() {
+            DeValue::Table(table) => Ok(table),
+            other => Err(self.error(key, value.span(), &format!("expected a table, found {}", other.type_str()))),
+        }
+    }
+
+    fn string(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<String, ConfigError> {
+        match value.get_ref() {
+            DeValue::String(text) => Ok(text.to_string()),
+            other => Err(self.error(key, value.span(), &format!("expected a string, found {}", other.type_str()))),
+        }
+    }
+
+    /// Reads an array of strings, or a single string
+    fn strings(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<Vec<String>, ConfigError> {
+        match value.get_ref() {
+            DeValue::String(text) => Ok(vec![text.to_string()]),
+            DeValue::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| self.string(&format!("{}[{}]", key, index), item))
+                .collect(),
+            other => Err(self.error(
+                key,
+                value.span(),
+                &format!("expected an array of strings, found {}", other.type_str()),
+            )),
+        }
+    }
+
+    fn boolean(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<bool, ConfigError> {
+        value
+            .get_ref()
+            .as_bool()
+            .ok_or_else(|| self.error(key, value.span(), &format!("expected a boolean, found {}", value.get_ref().type_str())))
+    }
+
+    fn percentage(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<f64, ConfigError> {
+        let number = match value.get_ref() {
+            DeValue::Integer(integer) if integer.radix() == 10 => integer.as_str().replace('_', "").parse::<f64>().ok(),
+            DeValue::Float(float) => float.as_str().replace('_', "").parse::<f64>().ok(),
+            _ => None,
+        };
+        number
+            .filter(|n| (0.0..=100.0).contains(n))
+            .ok_or_else(|| self.error(key, value.span(), "expected a percentage between 0 and 100"))
+    }
+
+    fn choice<T: Copy>(&self, key: &str, value: &Spanned<DeValue<'_>>, choices: &[(&str, T)]) -> Result<T, ConfigError> {
+        let text = self.string(key, value)?;
+        choices
+            .iter()
+            .find(|(name, _)| *name == text)
+            .map(|(_, choice)| *choice)
+            .ok_or_else(|| {
+                let names = choices.iter().map(|(name, _)| format!("`{}`", name)).collect::<Vec<_>>();
+                self.error(
+                    key,
+                    value.span(),
+                    &format!("expected one of {}, found `{}`", names.join(", "), text),
+                )
+            })
+    }
+
+    /// Reads globs, compiled once for every path they are matched against
+    fn globs(&self, key: &str, value: &Spanned<DeValue<'_>>, separator: char) -> Result<Vec<Glob>, ConfigError> {
+        Ok(self.strings(key, value)?.iter().map(|g| Glob::new(g, separator)).collect())
+    }
+
+    fn docstring_style(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<DocstringStyle, ConfigError> {
+        self.choice(
+            key,
+            value,
+            &[
+                ("auto", DocstringStyle::Auto),
+                ("google", DocstringStyle::Google),
+                ("numpy", DocstringStyle::Numpy),
+                ("sphinx", DocstringStyle::Sphinx),
+            ],
+        )
+    }
+
+    fn private_members(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<PrivateMembers, ConfigError> {
+        self.choice(
+            key,
+            value,
+            &[("include", PrivateMembers::Include), ("exclude", PrivateMembers::Exclude)],
+        )
+    }
+
+    fn module_override(&self, key: &str, value: &Spanned<DeValue<'_>>) -> Result<ModuleOverride, ConfigError> {
+        let table = self.table(key, value)?;
+        let mut entry = ModuleOverride {
+            modules: Vec::new(),
+            skip: false,
+            docstring_style: None,
+            private_members: None,
+            fail_under: None,
+        };
+        for (name, value) in table.iter() {
+            let path = format!("{}.{}", key, name.get_ref());
+            match name.get_ref().as_ref() {
+                "modules" => entry.modules = self.globs(&path, value, '.')?,
+                "skip" => entry.skip = self.boolean(&path, value)?,
+                "docstring-style" => entry.docstring_style = Some(self.docstring_style(&path, value)?),
+                "private-members" => entry.private_members = Some(self.private_members(&path, value)?),
+                "fail-under" => entry.fail_under = Some(self.percentage(&path, value)?),
+                _ => return Err(self.unknown_key(&path, name)),
+            }
+        }
+        if entry.modules.is_empty() {
+            return Err(self.error(key, value.span(), "missing key `modules`"));
+        }
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::Module;
+
+    const PYPROJECT: &str = r#"
+[project]
+name = "pkg"
+
+[tool.chapter]
+source-roots = ["src"]
+exclude = ["**/tests/**", "src/pkg/_build.py"]
+docstring-style = "google"
+output-format = "html"
+templates = "templates"
+
+[tool.chapter.coverage]
+fail-under = 80
+module-fail-under = 50.5
+
+[[tool.chapter.overrides]]
+modules = "pkg.legacy.*"
+docstring-style = "sphinx"
+private-members = "include"
+fail-under = 0
+
+[[tool.chapter.overrides]]
+modules = ["pkg._vendor", "pkg._vendor.**"]
+skip = true
+private-members = "include"
+"#;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(PYPROJECT, Path::new("project/pyproject.toml")).unwrap();
+        assert_eq!(config.root, PathBuf::from("project"));
+        assert_eq!(config.source_roots, vec![PathBuf::from("src")]);
+        assert_eq!(config.output_format, OutputFormat::Html);
+        assert_eq!(config.output_path(), PathBuf::from("project/docs/api"));
+        assert_eq!(config.coverage.fail_under, Some(80.0));
+
+        assert_eq!(config.docstring_style("pkg.core"), DocstringStyle::Google);
+        assert_eq!(config.docstring_style("pkg.legacy.io"), DocstringStyle::Sphinx);
+        assert_eq!(config.docstring_style("pkg.legacy"), DocstringStyle::Google);
+        assert_eq!(config.private_members("pkg.legacy.io"), PrivateMembers::Include);
+        assert_eq!(config.private_members("pkg.legacy"), PrivateMembers::Exclude);
+        assert_eq!(config.fail_under("pkg.core"), Some(50.5));
+        assert_eq!(config.fail_under("pkg.legacy.io"), Some(0.0));
+        assert!(config.is_skipped("pkg._vendor.six.moves"));
+        assert!(!config.is_skipped("pkg.vendor"));
+        assert_eq!(config.private_members("pkg._vendor"), PrivateMembers::Include);
+        assert_eq!(config.private_members("pkg.core"), PrivateMembers::Exclude);
+
+        assert!(config.is_included("src/pkg/core.py"));
+        assert!(config.is_included("src/pkg/core.pyi"));
+        assert!(!config.is_included("src/pkg/tests/test_core.py"));
+        assert!(!config.is_included("src/pkg/_build.py"));
+        assert!(!config.is_included("src/pkg/data.json"));
+
+        let config = Config::parse("[project]\nname = \"pkg\"\n", Path::new("pyproject.toml")).unwrap();
+        assert_eq!(config, Config { root: PathBuf::from(""), ..Config::default() });
+        let config = Config::parse("output-dir = \"site\"\n", Path::new("chapter.toml")).unwrap();
+        assert_eq!(config.output_dir, PathBuf::from("site"));
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let error = |source: &str| Config::parse(source, Path::new("pyproject.toml")).unwrap_err().to_string();
+        assert_eq!(
+            error("[tool.chapter]\noutput-dir = \"docs\"\noutput-fromat = \"html\"\n"),
+            "pyproject.toml:3: `tool.chapter.output-fromat`: unknown key"
+        );
+        assert_eq!(
+            error("[tool.chapter]\noutput-format = \"pdf\"\n"),
+            "pyproject.toml:2: `tool.chapter.output-format`: expected one of `markdown`, `html`, found `pdf`"
+        );
+        assert_eq!(
+            error("[tool.chapter]\nexclude = [\"tests\", 3]\n"),
+            "pyproject.toml:2: `tool.chapter.exclude[1]`: expected a string, found integer"
+        );
+        assert_eq!(
+            error("[tool.chapter.coverage]\n\nfail-under = 120\n"),
+            "pyproject.toml:3: `tool.chapter.coverage.fail-under`: expected a percentage between 0 and 100"
+        );
+        assert_eq!(
+            error("[[tool.chapter.overrides]]\nmodules = \"pkg.*\"\nskip = \"yes\"\n"),
+            "pyproject.toml:3: `tool.chapter.overrides[0].skip`: expected a boolean, found string"
+        );
+        assert_eq!(
+            error("[[tool.chapter.overrides]]\nmodules = \"pkg.*\"\ndocstring-style = \"epydoc\"\n"),
+            "pyproject.toml:3: `tool.chapter.overrides[0].docstring-style`: expected one of `auto`, `google`, `numpy`, `sphinx`, found `epydoc`"
+        );
+        assert!(error("[tool.chapter\n").starts_with("pyproject.toml:1: "));
+    }
+
+    #[test]
+    fn test_load_package() {
+        let root = std::env::temp_dir().join(format!("chapter-config-{}", std::process::id()));
+        let files = [
+            ("pyproject.toml", PYPROJECT),
+            ("src/pkg/__init__.py", "from .core import Client\n"),
+            ("src/pkg/core.py", "class Client:\n    def _connect(self):\n        pass\n"),
+            ("src/pkg/tests/test_core.py", "def test_client():\n    pass\n"),
+            ("src/pkg/_vendor/six.py", "PY3 = True\n"),
+            (
+                "src/pkg/legacy/io.py",
+                "\"\"\"Legacy IO.\"\"\"\ndef _open():\n    pass\ndef read():\n    pass\ndef write():\n    pass\n",
+            ),
+        ];
+        for (path, source) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+
+        let config = Config::discover(&root.join("src/pkg")).unwrap().unwrap();
+        let package = config.load_package();
+        fs::remove_dir_all(&root).unwrap();
+
+        let package = package.unwrap();
+        assert_eq!(package.modules.keys().collect::<Vec<_>>(), vec!["pkg", "pkg.core", "pkg.legacy.io"]);
+        let client = package.class("pkg.core.Client").unwrap();
+        assert!(client.borrow().children.is_empty());
+        assert_eq!(package.module("pkg.legacy.io").unwrap().docstring_style, DocstringStyle::Sphinx);
+
+        let failures = config
+            .check_coverage(&package)
+            .iter()
+            .map(|f| f.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failures,
+            vec![
+                "pkg: 0.0% of the public symbols are documented, expected at least 50.5%",
+                "pkg.core: 0.0% of the public symbols are documented, expected at least 50.5%",
+                "package: 16.7% of the public symbols are documented, expected at least 80%",
+            ]
+        );
+
+        let mut package = package;
+        package.add_module(Module::parse("pkg.core", "def _helper():\n    pass\n"));
+        // The parsed `pkg.core` keeps `_helper`, hidden by the global policy
+        let config = Config { templates: None, output_format: OutputFormat::Markdown, ..config };
+        let renderer = config.renderer(&package).unwrap();
+        assert!(renderer.render_module(package.module("pkg.legacy.io").unwrap()).unwrap().contains("_open"));
+        assert!(!renderer.render_module(package.module("pkg.core").unwrap()).unwrap().contains("_helper"));
+    }
+}
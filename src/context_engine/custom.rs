@@ -0,0 +1,94 @@
+use regex::Regex;
+
+use super::definitions::ContextType;
+
+type Matcher = Box<dyn Fn(&str) -> bool>;
+type Extractor = Box<dyn Fn(&str) -> Option<String>>;
+
+/// User defined context recorded as a leaf node of the context tree.
+///
+/// Custom contexts are checked against every statement that is not a class,
+/// def or `__all__` header, before the built-in statements such as imports,
+/// variables or attributes. The matcher and the extractors receive the whole
+/// statement: lines continued inside brackets are joined with newlines and
+/// decorators are followed by the lines up to the decorated header. By
+/// default the node has no name and the value holds the trimmed statement.
+///
+/// ```
+/// use chapter::context_engine::custom::CustomContext;
+///
+/// let django_field = CustomContext::from_pattern(
+///     "django_field",
+///     r"^\s*(?P<name>\w+)\s*=\s*models\.(?P<value>\w+)\(",
+/// )
+/// .unwrap();
+/// let fixture = CustomContext::new("pytest_fixture", |s| s.trim_start().starts_with("@pytest.fixture"))
+///     .with_name(|s| s.lines().last()?.split("def ").nth(1)?.split('(').next().map(String::from));
+/// ```
+pub struct CustomContext {
+    kind: String,
+    matcher: Matcher,
+    name_extractor: Extractor,
+    value_extractor: Extractor,
+}
+
+impl CustomContext {
+    pub fn new(kind: &str, matcher: impl Fn(&str) -> bool + 'static) -> Self {
+        Self {
+            kind: kind.to_string(),
+            matcher: Box::new(matcher),
+            name_extractor: Box::new(|_| None),
+            value_extractor: Box::new(|statement| Some(statement.trim().to_string())),
+        }
+    }
+
+    /// Creates a context matching a regex. The `name` and `value` named
+    /// groups, when present, are used as the node name and value.
+    pub fn from_pattern(kind: &str, pattern: &str) -> Result<Self, regex::Error> {
+        let regex = Regex::new(pattern)?;
+        let name_regex = regex.clone();
+        let value_regex = regex.clone();
+        Ok(Self::new(kind, move |statement| regex.is_match(statement))
+            .with_name(move |statement| capture(&name_regex, statement, "name"))
+            .with_value(move |statement| capture(&value_regex, statement, "value")))
+    }
+
+    /// Sets the function extracting the node name from the statement.
+    pub fn with_name(mut self, extractor: impl Fn(&str) -> Option<String> + 'static) -> Self {
+        self.name_extractor = Box::new(extractor);
+        self
+    }
+
+    /// Sets the function extracting the node value from the statement.
+    pub fn with_value(mut self, extractor: impl Fn(&str) -> Option<String> + 'static) -> Self {
+        self.value_extractor = Box::new(extractor);
+        self
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn context_type(&self) -> ContextType {
+        ContextType::CUSTOM(self.kind.clone())
+    }
+
+    pub fn matches(&self, statement: &str) -> bool {
+        (self.matcher)(statement)
+    }
+
+    pub fn name(&self, statement: &str) -> Option<String> {
+        (self.name_extractor)(statement)
+    }
+
+    pub fn value(&self, statement: &str) -> Option<String> {
+        (self.value_extractor)(statement)
+    }
+}
+
+fn capture(regex: &Regex, statement: &str, group: &str) -> Option<String> {
+    regex
+        .captures(statement)?
+        .name(group)
+        .map(|m| m.as_str().to_string())
+}
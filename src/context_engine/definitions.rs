@@ -1,5 +1,10 @@
 use std::{rc::Rc, cell::RefCell};
 
+use super::syntax::{
+    Attribute, ClassHeader, Deprecation, EnumMember, FunctionSignature, Guard, ImportStatement,
+    Property, Variable,
+};
+
 pub type CodeLocation = (usize, usize);
 
 /// NamespaceType refers to a code block context type
@@ -19,16 +24,121 @@ pub enum ContextType {
 
     /// Represents a docstring context
     DOCSTRING,
+
+    /// Represents an import statement
+    IMPORT,
+
+    /// Represents a module level variable or constant
+    VARIABLE,
+
+    /// Represents a type alias, explicit or assigned a typing construct
+    ALIAS,
+
+    /// Represents a class attribute, instance attribute, slot or field
+    ATTRIBUTE,
+
+    /// Represents a property, merging its getter, setter and deleter
+    PROPERTY,
+
+    /// Represents a user registered context, identified by its kind
+    CUSTOM(String),
+}
+
+/// Start and end coordinates of a piece of source code.
+///
+/// Lines and columns are zero based. Columns count characters inside the
+/// line while offsets count bytes from the beginning of the module, so the
+/// offsets can be used to slice the original source text. Coordinates that
+/// are not known (yet) are left as `None`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Hash)]
+pub struct Span {
+    pub start_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_line: Option<usize>,
+    pub end_column: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+impl Span {
+    pub fn set_start(&mut self, line: usize, column: usize, offset: Option<usize>) {
+        self.start_line = Some(line);
+        self.start_column = Some(column);
+        self.start_offset = offset;
+    }
+
+    pub fn set_end(&mut self, line: usize, column: usize, offset: Option<usize>) {
+        self.end_line = Some(line);
+        self.end_column = Some(column);
+        self.end_offset = offset;
+    }
+
+    /// Returns the start and end lines if both are known.
+    pub fn lines(&self) -> Option<CodeLocation> {
+        Some((self.start_line?, self.end_line?))
+    }
+
+    /// Returns the text covered by the span, if the byte offsets are known
+    /// and fall on character boundaries of the given source.
+    pub fn slice<'a>(&self, source: &'a str) -> Option<&'a str> {
+        source.get(self.start_offset?..self.end_offset?)
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct ContextNode {
     pub name: String,
     pub context_type: ContextType,
-    start: usize,
-    end: usize,
+
+    /// Whole definition, from the first character of the header to the end
+    /// of the last line of the body
+    pub span: Span,
+
+    /// Name token of the definition
+    pub name_span: Span,
+
+    /// Definition header, from the keyword up to and including the colon
+    pub signature_span: Span,
+
+    /// Block following the header
+    pub body_span: Span,
     pub is_public: bool,
     pub value: Option<String>,
+
+    /// Bases, keywords and type parameters of class definitions
+    pub class_header: Option<ClassHeader>,
+
+    /// Parameters and return annotation of method definitions. Overloaded
+    /// functions without an implementation have none.
+    pub signature: Option<FunctionSignature>,
+
+    /// Signatures of the `@overload` variants of a function, in source order
+    pub overloads: Vec<FunctionSignature>,
+
+    /// Modules and names bound by import statements
+    pub import: Option<ImportStatement>,
+
+    /// Annotation and value of variables and type aliases
+    pub variable: Option<Variable>,
+
+    /// Kind, default and field options of class attributes
+    pub attribute: Option<Attribute>,
+
+    /// Accessors and type of properties
+    pub property: Option<Property>,
+
+    /// Deprecation declared by a decorator, a warning or the docstring
+    pub deprecation: Option<Deprecation>,
+
+    /// Decorator expressions of classes and methods, without the `@`
+    pub decorators: Vec<String>,
+
+    /// Members of enum classes in definition order, `None` for any node that
+    /// is not an enum class
+    pub enum_members: Option<Vec<EnumMember>>,
+
+    /// Control flow blocks between the node and its parent, outermost first
+    pub guards: Vec<Guard>,
     pub children: Vec<Rc<RefCell<ContextNode>>>,
     pub parent: Option<Rc<RefCell<ContextNode>>>
 }
@@ -38,28 +148,57 @@ impl ContextNode {
         self.children.push(child_node);
     }
 
-    pub fn append_value(&mut self, new_value: &String) {
-        match self.value.to_owned() {
-            Some(mut v) => v.push_str(new_value),
+    pub fn append_value(&mut self, new_value: &str) {
+        match self.value.as_mut() {
+            Some(v) => v.push_str(new_value),
             None => self.value = Some(new_value.to_owned())
         }
     }
 
+    /// Returns the bare name of the definition, without the `class` or
+    /// `def` keyword.
+    pub fn identifier(&self) -> &str {
+        self.name.rsplit(' ').next().unwrap_or(&self.name)
+    }
+
+    /// Returns the text of the docstring attached to the node, without
+    /// quotes and with the common indentation removed.
+    pub fn docstring(&self) -> Option<String> {
+        self.children
+            .iter()
+            .find(|c| c.borrow().context_type == ContextType::DOCSTRING)
+            .and_then(|c| c.borrow().value.as_deref().map(clean_docstring))
+    }
+
     pub fn location(&self) -> Option<CodeLocation>{
-        if self.end != usize::MIN {
-            return Some((self.start, self.end))
-        }
-        None
+        self.span.lines()
     }
 
     pub fn new(name: String, context_type: ContextType, start: usize, is_public: bool) -> Rc<RefCell<Self>> {
+        let span = Span {
+            start_line: Some(start),
+            ..Span::default()
+        };
         Rc::new(RefCell::new(ContextNode {
             name,
             context_type,
-            start,
-            end: usize::MIN,
+            span,
+            name_span: Span::default(),
+            signature_span: Span::default(),
+            body_span: Span::default(),
             is_public,
             value: None,
+            class_header: None,
+            signature: None,
+            overloads: Vec::new(),
+            import: None,
+            variable: None,
+            attribute: None,
+            property: None,
+            deprecation: None,
+            decorators: Vec::new(),
+            enum_members: None,
+            guards: Vec::new(),
             children: Vec::new(),
             parent: None
         }))
@@ -69,18 +208,31 @@ impl ContextNode {
         Rc::new(RefCell::new(ContextNode {
             name: String::from("__root__"),
             context_type: ContextType::ROOT,
-            start: usize::MIN,
-            end: usize::MIN,
+            span: Span::default(),
+            name_span: Span::default(),
+            signature_span: Span::default(),
+            body_span: Span::default(),
             is_public: false,
             value: None,
+            class_header: None,
+            signature: None,
+            overloads: Vec::new(),
+            import: None,
+            variable: None,
+            attribute: None,
+            property: None,
+            deprecation: None,
+            decorators: Vec::new(),
+            enum_members: None,
+            guards: Vec::new(),
             children: Vec::new(),
             parent: None
         }))
     }
 
     pub fn set_location(&mut self, end: usize) {
-        if self.end == usize::MIN {
-            self.end = end
+        if self.span.end_line.is_none() {
+            self.span.end_line = Some(end)
         }
     }
 
@@ -89,6 +241,40 @@ impl ContextNode {
     }
 }
 
+/// Removes the quotes around a docstring literal and the indentation common to
+/// its lines, leaving the first line as is (like `inspect.cleandoc`).
+pub fn clean_docstring(raw: &str) -> String {
+    let text = raw.trim().trim_start_matches(['r', 'R', 'u', 'U']);
+    let quote = ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find(|q| text.starts_with(q))
+        .unwrap_or("");
+    let text = text.strip_prefix(quote).unwrap_or(text);
+    let text = text.strip_suffix(quote).unwrap_or(text);
+
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or_default().trim().to_string();
+    let rest = lines.collect::<Vec<&str>>();
+    let margin = rest
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut cleaned = vec![first];
+    cleaned.extend(
+        rest.iter()
+            .map(|l| l.get(margin..).unwrap_or("").trim_end().to_string()),
+    );
+    while cleaned.last().is_some_and(|l| l.is_empty()) {
+        cleaned.pop();
+    }
+    while cleaned.first().is_some_and(|l| l.is_empty()) {
+        cleaned.remove(0);
+    }
+    cleaned.join("\n")
+}
 
 pub(super) struct Indent {
     value: String,
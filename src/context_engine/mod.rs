@@ -1,17 +1,30 @@
+pub mod custom;
 pub mod definitions;
+pub mod observer;
 mod processor;
 pub(super) mod state;
 pub mod rules;
+pub mod syntax;
 
+pub use observer::{ParseEvent, ParseObserver};
 pub use processor::ContextProcessor;
 
-const DOCSTRING_START: &str = r#"\s{4}"{3}\w*|\s{4}"{3}"#;
-const DEF_MATCH: &str = r"def\s\w+";
-const CLASS_MATCH: &str = r"class\s\w+";
-const ALL_MATCH: &str = r"__all__\s=\s(\[|\()";
+// Entry patterns are anchored at the start of the statement so that keywords
+// appearing later in a line (strings, comments, expressions) never open a
+// context. The `ident` group holds the name of the definition.
+const DOCSTRING_START: &str = r#"^\s*[rRuU]?("{3}|'{3})"#;
+const DEF_MATCH: &str = r"^\s*(?:async\s+)?def\s+(?P<ident>\w+)";
+const CLASS_MATCH: &str = r"^\s*class\s+(?P<ident>\w+)";
+const ALL_MATCH: &str = r"^__all__\s*(?::[^=]*)?=\s*(\[|\()";
+const IMPORT_MATCH: &str = r"^\s*(?:from\s+\.*[\w.]*\s+import\b|import\s+\w)";
+const TYPE_ALIAS_MATCH: &str = r"^\s*type\s+[A-Za-z_]";
+const VARIABLE_MATCH: &str = r"^\s*[A-Za-z_]\w*\s*(?::|=[^=])";
+const INSTANCE_ATTRIBUTE_MATCH: &str = r"^\s*self\.\w+\s*(?::|=[^=])";
+const TYPE_CHECKING_MATCH: &str = r"^(?:el)?if\s+(?:typing\.)?TYPE_CHECKING\s*:";
 const DOCSTRING_END: &str = r#"('{3}|"{3})$"#;
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::unnecessary_to_owned)]
 mod tests {
     use std::rc::Rc;
 
@@ -228,6 +241,859 @@ def outscope_method():
         assert_eq!(module.borrow().children.len(), 3);
     }
 
+    #[test]
+    fn test_location_on_first_line() {
+        let node = definitions::ContextNode::new(
+            "TestClass".to_string(),
+            definitions::ContextType::CLASS,
+            0,
+            true,
+        );
+
+        node.borrow_mut().set_location(0);
+        assert_eq!(node.borrow().location(), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_parse_module_spans() {
+        let source = "import os
+
+class TestClass(Base):
+    \"\"\"Class docstring\"\"\"
+
+    def method(self,
+               value: int) -> str:
+        text = \"\"\"
+class NotAClass:
+\"\"\"
+        return text
+# trailing comment
+def one_liner(): pass
+";
+        let text_code = source
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+        let module = processor.parse_module();
+        let module = module.borrow();
+
+        assert_eq!(module.children.len(), 3);
+        assert_eq!(module.children[0].borrow().name, "os");
+        let class_node = module.children[1].borrow();
+        assert_eq!(class_node.location(), Some((2, 10)));
+        assert_eq!(class_node.name_span.slice(source), Some("TestClass"));
+        assert_eq!(
+            class_node.signature_span.slice(source),
+            Some("class TestClass(Base):")
+        );
+        assert_eq!(class_node.children.len(), 2);
+
+        let docstring = class_node.children[0].borrow();
+        assert_eq!(
+            docstring.span.slice(source),
+            Some("\"\"\"Class docstring\"\"\"")
+        );
+
+        let method = class_node.children[1].borrow();
+        assert_eq!(method.span.start_column, Some(4));
+        assert_eq!(
+            method.signature_span.slice(source),
+            Some("def method(self,\n               value: int) -> str:")
+        );
+        assert_eq!(method.body_span.start_line, Some(7));
+        assert_eq!(method.body_span.end_line, Some(10));
+        assert_eq!(method.body_span.end_column, Some(19));
+
+        let one_liner = module.children[2].borrow();
+        assert_eq!(one_liner.location(), Some((12, 12)));
+        assert_eq!(one_liner.body_span.slice(source), Some("pass"));
+    }
+
+    #[test]
+    fn test_parse_module_observer_events() {
+        let text_code = "
+class TestClass:
+    def method(self):
+        \"\"\"Never closed
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let events = Rc::new(std::cell::RefCell::new(Vec::new()));
+        let recorded = Rc::clone(&events);
+        let mut processor = ContextProcessor::load(text_code)
+            .with_observer(move |event: &ParseEvent| recorded.borrow_mut().push(event.clone()));
+
+        processor.parse_module();
+
+        let events = events.borrow();
+        assert_eq!(
+            events[0],
+            ParseEvent::Enter {
+                context_type: definitions::ContextType::CLASS,
+                name: "class TestClass".to_string(),
+                line: 1,
+                depth: 1,
+            }
+        );
+        assert_eq!(
+            events[3],
+            ParseEvent::Diagnostic {
+                line: 3,
+                message: "docstring is never closed".to_string(),
+            }
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, ParseEvent::Exit { .. }))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_parse_module_with_rules() {
+        let text_code = "
+class TestClass:
+    \"\"\"Docstring\"\"\"
+
+    def __init__(self):
+        def inner():
+            pass
+
+    def _private(self):
+        pass
+
+    class Inner:
+        def deep(self):
+            pass
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let rules = rules::LogicContext::new()
+            .track(vec![
+                definitions::ContextType::CLASS,
+                definitions::ContextType::METHOD,
+            ])
+            .nested_functions(false)
+            .max_depth(2)
+            .keep_private(false);
+        let mut processor = ContextProcessor::load(text_code).with_rules(rules);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let class_node = module.children[0].borrow();
+        let names = class_node
+            .children
+            .iter()
+            .map(|c| c.borrow().name.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["def __init__", "class Inner"]);
+        assert!(class_node.children[0].borrow().children.is_empty());
+        assert!(class_node.children[1].borrow().children.is_empty());
+    }
+
+    #[test]
+    fn test_parse_module_max_depth_docstrings() {
+        let text_code = "
+class TestClass:
+    \"\"\"Docstring\"\"\"
+
+    def method(self):
+        \"\"\"Method docstring\"\"\"
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let rules = rules::LogicContext::new().max_depth(1);
+        let mut processor = ContextProcessor::load(text_code).with_rules(rules);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let class_node = module.children[0].borrow();
+        assert_eq!(class_node.children.len(), 1);
+        assert_eq!(class_node.docstring(), Some(String::from("Docstring")));
+    }
+
+    #[test]
+    fn test_parse_module_custom_contexts() {
+        let text_code = "
+class Article(models.Model):
+    title = models.CharField(
+        max_length=200,
+    )
+    _slug = models.SlugField()
+
+@app.get(\"/articles\")
+async def list_articles():
+    pass
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let django_field = custom::CustomContext::from_pattern(
+            "django_field",
+            r"^\s*(?P<name>\w+)\s*=\s*models\.(?P<value>\w+)\(",
+        )
+        .unwrap();
+        let route = custom::CustomContext::new("route", |s| s.starts_with("@app."))
+            .with_name(|s| s.split("def ").nth(1)?.split('(').next().map(String::from));
+        let mut processor = ContextProcessor::load(text_code)
+            .register_context(django_field)
+            .register_context(route);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let fields = &module.children[0].borrow().children;
+        assert_eq!(fields.len(), 2);
+        let title = fields[0].borrow();
+        assert_eq!(
+            title.context_type,
+            definitions::ContextType::CUSTOM("django_field".to_string())
+        );
+        assert_eq!(title.name, "title");
+        assert_eq!(title.value, Some("CharField".to_string()));
+        assert_eq!(title.location(), Some((2, 4)));
+        assert!(!fields[1].borrow().is_public);
+
+        let route = module.children[1].borrow();
+        assert_eq!(route.name, "list_articles");
+        assert_eq!(
+            route.value,
+            Some("@app.get(\"/articles\")\nasync def list_articles():".to_string())
+        );
+        assert_eq!(module.children[2].borrow().name, "def list_articles");
+    }
+
+    const DETERMINISM_CORPUS: &str = r#"# Lines matching several entry patterns
+"""Module docstring mentioning class Foo and def bar."""
+
+__all__ = ["classify", "Shape"]
+
+
+def classify(value):
+    """Returns the class of a value, like def or class."""
+    return "class Foo" if value else "def bar"
+
+
+class Shape(Base):  # def in a comment
+    kind = "class"
+
+    async def area(self):
+        text = """
+class NotAClass:
+    def not_a_method(self): pass
+"""
+        return text
+
+    def _describe(self): return "def inline"
+"#;
+
+    fn outline(node: &Rc<std::cell::RefCell<definitions::ContextNode>>, depth: usize, out: &mut String) {
+        let node = node.borrow();
+        out.push_str(&format!(
+            "{}{:?} {} {:?}\n",
+            "  ".repeat(depth),
+            node.context_type,
+            node.name,
+            node.location()
+        ));
+        for child in &node.children {
+            outline(child, depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn test_parse_module_is_deterministic() {
+        let parse = || {
+            let text_code = DETERMINISM_CORPUS
+                .split('\n')
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>();
+            let mut out = String::new();
+            outline(&ContextProcessor::load(text_code).parse_module(), 0, &mut out);
+            out
+        };
+
+        let expected = "ROOT __root__ Some((0, 21))
+  DOCSTRING __empty__ Some((1, 1))
+  ALL __all__ Some((3, 3))
+  METHOD def classify Some((6, 8))
+    DOCSTRING __empty__ Some((7, 7))
+  CLASS class Shape Some((11, 21))
+    ATTRIBUTE kind Some((12, 12))
+    METHOD def area Some((14, 19))
+    METHOD def _describe Some((21, 21))
+";
+        for _ in 0..20 {
+            assert_eq!(parse(), expected);
+        }
+    }
+
+    #[test]
+    fn test_class_header_parse() {
+        let header = syntax::ClassHeader::parse("class Box[T: int, *Ts](Base[T], metaclass=ABCMeta, total=False):");
+        assert_eq!(header.type_params, vec!["T: int", "*Ts"]);
+        assert_eq!(header.bases, vec!["Base[T]"]);
+        assert_eq!(header.metaclass, Some("ABCMeta".to_string()));
+        assert_eq!(header.keywords, vec![("total".to_string(), "False".to_string())]);
+        assert_eq!(header.generic_parameters(), vec!["T", "Ts"]);
+
+        let header = syntax::ClassHeader::parse("class Foo(typing.Generic[K, V], Mapping[K, V]):");
+        assert_eq!(header.generic_parameters(), vec!["K", "V"]);
+        assert!(syntax::ClassHeader::parse("class Plain:").bases.is_empty());
+    }
+
+    #[test]
+    fn test_parse_module_class_headers() {
+        let text_code = "
+class TestClass(TestInterface):
+    pass
+
+class Wrapped(
+    Base,  # comment, with a comma
+    metaclass=Meta,
+):
+    pass
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let header = module.children[0].borrow().class_header.clone().unwrap();
+        assert_eq!(header.bases, vec!["TestInterface"]);
+        let header = module.children[1].borrow().class_header.clone().unwrap();
+        assert_eq!(header.bases, vec!["Base"]);
+        assert_eq!(header.metaclass, Some("Meta".to_string()));
+    }
+
+    #[test]
+    fn test_parse_module_imports() {
+        let text_code = "
+import os.path as osp, sys
+from ..models import (
+    User,
+    Group as UserGroup,  # comment
+)
+
+if TYPE_CHECKING:
+    from .types import Alias
+
+try:
+    import ujson as json
+except ImportError:
+    import json
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let imports = module
+            .borrow()
+            .children
+            .iter()
+            .map(|c| c.borrow().import.clone().unwrap())
+            .collect::<Vec<syntax::ImportStatement>>();
+        assert_eq!(imports.len(), 5);
+        assert_eq!(imports[0].names[0].bound_name(), "osp");
+        assert_eq!(imports[0].names[1].bound_name(), "sys");
+        assert!(!imports[0].is_from);
+        assert_eq!(imports[1].module, "models");
+        assert_eq!(imports[1].level, 2);
+        assert_eq!(imports[1].source_module(), "..models");
+        assert_eq!(
+            imports[1].names[1],
+            syntax::ImportedName {
+                name: "Group".to_string(),
+                alias: Some("UserGroup".to_string()),
+            }
+        );
+        assert!(imports[2].type_checking);
+        assert!(!imports[1].type_checking && !imports[1].import_error_guard);
+        assert!(imports[3].import_error_guard);
+        assert!(imports[4].import_error_guard);
+    }
+
+    #[test]
+    fn test_parse_module_variables() {
+        let text_code = r#"
+DEFAULT_TIMEOUT: int = 30
+"""Seconds to wait for a response."""
+Color = Literal["red", "blue"]
+type Pair[T] = tuple[T, T]
+T = TypeVar("T", bound=int)
+_cache: dict[str, int] = {
+    "a": 1,
+}
+'Attribute docstring in single quotes.'
+if DEFAULT_TIMEOUT == 30:
+    pass
+
+def function():
+    local = 1
+"#
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let names = module
+            .children
+            .iter()
+            .map(|c| (c.borrow().context_type.clone(), c.borrow().name.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (definitions::ContextType::VARIABLE, "DEFAULT_TIMEOUT".to_string()),
+                (definitions::ContextType::ALIAS, "Color".to_string()),
+                (definitions::ContextType::ALIAS, "Pair".to_string()),
+                (definitions::ContextType::VARIABLE, "T".to_string()),
+                (definitions::ContextType::VARIABLE, "_cache".to_string()),
+                (definitions::ContextType::METHOD, "def function".to_string()),
+            ]
+        );
+
+        let timeout = module.children[0].borrow();
+        let variable = timeout.variable.clone().unwrap();
+        assert_eq!(variable.annotation, Some("int".to_string()));
+        assert_eq!(variable.value, Some("30".to_string()));
+        assert_eq!(
+            timeout.docstring(),
+            Some("Seconds to wait for a response.".to_string())
+        );
+
+        let pair = module.children[2].borrow().variable.clone().unwrap();
+        assert_eq!(pair.type_params, vec!["T"]);
+        assert_eq!(pair.value, Some("tuple[T, T]".to_string()));
+        assert!(module.children[3].borrow().variable.clone().unwrap().is_type_variable());
+
+        let cache = module.children[4].borrow();
+        assert!(!cache.is_public);
+        assert_eq!(cache.location(), Some((6, 8)));
+        assert_eq!(
+            cache.variable.clone().unwrap().value,
+            Some("{\n    \"a\": 1,\n}".to_string())
+        );
+        assert_eq!(
+            cache.docstring(),
+            Some("Attribute docstring in single quotes.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_is_data_class() {
+        let decorated = |decorator: &str| syntax::is_data_class(&[decorator.to_string()], None);
+        assert!(decorated("dataclass"));
+        assert!(decorated("dataclasses.dataclass(frozen=True)"));
+        assert!(decorated("attr.s(auto_attribs=True)"));
+        assert!(decorated("attrs.frozen"));
+        assert!(!decorated("define"));
+        assert!(!decorated("registry.frozen"));
+        assert!(!decorated("s"));
+    }
+
+    #[test]
+    fn test_deprecation_message() {
+        let deprecation = |message: &str| syntax::Deprecation::from_decorator(&format!("deprecated({:?})", message)).unwrap();
+        let users = deprecation("Users should migrate, use `fetch` instead. Removed in 3.0.");
+        assert_eq!(users.replacement.as_deref(), Some("fetch"));
+        assert_eq!(users.removal.as_deref(), Some("3.0"));
+        let used = deprecation("This used to be the default");
+        assert_eq!(used.replacement, None);
+        let unicode = deprecation("İstanbul İstemcisi kaldırıldı, USE `yeni_istemci`; removal in 4.0");
+        assert_eq!(unicode.replacement.as_deref(), Some("yeni_istemci"));
+        assert_eq!(unicode.removal.as_deref(), Some("4.0"));
+    }
+
+    #[test]
+    fn test_parse_module_attributes() {
+        let text_code = r#"
+@dataclasses.dataclass(frozen=True)
+# Immutable settings
+class Config:
+    name: str
+    retries: int = 3
+    """How many times to retry."""
+    tags: list[str] = field(default_factory=list, metadata={"unit": "tag"})
+    registry: ClassVar[dict] = {}
+
+class Model(BaseModel):
+    id: int = Field(..., gt=0)
+
+class Point:
+    __slots__ = {"x": "Horizontal position.", "y": "Vertical position."}
+
+class Service:
+    timeout = 10
+
+    def __init__(self, url: str):
+        self.url: str = url
+        """Address of the service."""
+        self.timeout = 5
+        local = 1
+"#
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let config = module.children[0].borrow();
+        assert_eq!(config.decorators, vec!["dataclasses.dataclass(frozen=True)"]);
+        let fields = config
+            .children
+            .iter()
+            .map(|c| {
+                let c = c.borrow();
+                (c.name.clone(), c.attribute.clone().unwrap())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0].1.kind, syntax::AttributeKind::Field);
+        assert_eq!(fields[1].1.default, Some("3".to_string()));
+        assert_eq!(
+            config.children[1].borrow().docstring(),
+            Some("How many times to retry.".to_string())
+        );
+        assert_eq!(fields[2].1.default_factory, Some("list".to_string()));
+        assert_eq!(
+            fields[2].1.field_arguments,
+            vec![("metadata".to_string(), "{\"unit\": \"tag\"}".to_string())]
+        );
+        assert_eq!(fields[3].1.kind, syntax::AttributeKind::Class);
+
+        let model_field = module.children[1].borrow().children[0].borrow().attribute.clone().unwrap();
+        assert_eq!(model_field.kind, syntax::AttributeKind::Field);
+        assert_eq!(model_field.default, None);
+        assert_eq!(model_field.field_arguments, vec![("gt".to_string(), "0".to_string())]);
+
+        let point = module.children[2].borrow();
+        assert_eq!(point.children.len(), 2);
+        assert_eq!(point.children[1].borrow().name, "y");
+        assert_eq!(
+            point.children[1].borrow().docstring(),
+            Some("Vertical position.".to_string())
+        );
+
+        let service = module.children[3].borrow();
+        let names = service
+            .children
+            .iter()
+            .map(|c| c.borrow().name.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["timeout", "def __init__", "url"]);
+        let url = service.children[2].borrow();
+        assert_eq!(url.attribute.clone().unwrap().kind, syntax::AttributeKind::Instance);
+        assert_eq!(url.variable.clone().unwrap().annotation, Some("str".to_string()));
+        assert_eq!(url.docstring(), Some("Address of the service.".to_string()));
+        assert!(service.children[1].borrow().children.is_empty());
+    }
+
+    #[test]
+    fn test_parse_module_enum_members() {
+        let text_code = r#"
+class Color(enum.Enum):
+    RED = "red"  # Primary colour
+    """Color of fire."""
+    _ignore_ = ["TEMP"]
+    # Shade of green
+    GREEN = auto()
+
+    def describe(self):
+        return self.value
+
+class Permission(Flag):
+    READ = auto()
+    WRITE = auto()
+    EXECUTE = 8
+    ALL = auto()
+
+class Plain:
+    VALUE = 1
+"#
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let color = module.children[0].borrow().enum_members.clone().unwrap();
+        assert_eq!(color.len(), 2);
+        assert_eq!(color[0].name, "RED");
+        assert_eq!(color[0].resolved_value, Some("\"red\"".to_string()));
+        assert_eq!(color[0].comment, Some("Primary colour".to_string()));
+        assert_eq!(color[0].docstring, Some("Color of fire.".to_string()));
+        assert!(color[1].is_auto());
+        assert_eq!(color[1].resolved_value, Some("1".to_string()));
+        assert_eq!(color[1].comment, Some("Shade of green".to_string()));
+        assert_eq!(color[1].line, 6);
+
+        let permission = module.children[1].borrow().enum_members.clone().unwrap();
+        let values = permission
+            .iter()
+            .map(|m| m.resolved_value.clone().unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(values, vec!["1", "2", "8", "16"]);
+
+        assert_eq!(module.children[2].borrow().enum_members, None);
+    }
+
+    const CONDITIONAL_CORPUS: &str = "
+if sys.version_info >= (3, 11):
+    def run():
+        pass
+elif sys.version_info >= (3, 8):
+    def run():
+        pass
+else:
+    def run():
+        pass
+
+class Loader:
+    try:
+        from fast import parse
+    except ImportError:
+        def parse(text):
+            pass
+
+    with lock:
+        CACHE = {}
+";
+
+    #[test]
+    fn test_parse_module_guards() {
+        let text_code = CONDITIONAL_CORPUS
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        assert_eq!(module.children.len(), 4);
+        let guards = &module.children[2].borrow().guards;
+        assert_eq!(guards.len(), 1);
+        assert_eq!(guards[0].kind, syntax::GuardKind::Else);
+        assert_eq!(
+            guards[0].previous_conditions,
+            vec!["sys.version_info >= (3, 11)", "sys.version_info >= (3, 8)"]
+        );
+
+        let loader = module.children[3].borrow();
+        let names = loader
+            .children
+            .iter()
+            .map(|c| c.borrow().name.clone())
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["fast", "def parse", "CACHE"]);
+        let parse_guard = &loader.children[1].borrow().guards[0];
+        assert_eq!(parse_guard.kind, syntax::GuardKind::Except);
+        assert_eq!(parse_guard.condition, Some("ImportError".to_string()));
+        assert_eq!(parse_guard.line, 14);
+        assert_eq!(
+            loader.children[2].borrow().guards[0].condition,
+            Some("lock".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_module_branch_selection() {
+        let text_code = CONDITIONAL_CORPUS
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let rules = rules::LogicContext::new()
+            .assume("sys.version_info >= (3, 11)", false)
+            .assume("sys.version_info >=  (3, 8)", true);
+        let mut processor = ContextProcessor::load(text_code).with_rules(rules);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        assert_eq!(module.children.len(), 2);
+        assert_eq!(module.children[0].borrow().location(), Some((5, 6)));
+        assert_eq!(
+            module.children[0].borrow().guards[0].kind,
+            syntax::GuardKind::Elif
+        );
+    }
+
+    #[test]
+    fn test_function_signature_parse() {
+        let signature = syntax::FunctionSignature::parse(
+            "async def fetch[T](self, key: str, /, retries: int = 3, *args, timeout=None, **kwargs) -> T:",
+        )
+        .unwrap();
+
+        assert!(signature.is_async);
+        assert_eq!(signature.type_params, vec!["T"]);
+        let kinds = signature.parameters.iter().map(|p| p.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                syntax::ParameterKind::PositionalOnly,
+                syntax::ParameterKind::PositionalOnly,
+                syntax::ParameterKind::PositionalOrKeyword,
+                syntax::ParameterKind::VarPositional,
+                syntax::ParameterKind::KeywordOnly,
+                syntax::ParameterKind::VarKeyword,
+            ]
+        );
+        assert_eq!(signature.parameters[2].annotation.as_deref(), Some("int"));
+        assert_eq!(signature.parameters[2].default.as_deref(), Some("3"));
+        assert_eq!(signature.return_annotation.as_deref(), Some("T"));
+        assert_eq!(
+            signature.render(),
+            "[T](self, key: str, /, retries: int = 3, *args, timeout=None, **kwargs) -> T"
+        );
+
+        let signature = syntax::FunctionSignature::parse("def f(a, *, b: dict[str, int] = {}):").unwrap();
+        assert_eq!(signature.parameters[1].kind, syntax::ParameterKind::KeywordOnly);
+        assert_eq!(signature.parameters[1].default.as_deref(), Some("{}"));
+        assert_eq!(signature.render(), "(a, *, b: dict[str, int] = {})");
+    }
+
+    #[test]
+    fn test_parse_module_overloads() {
+        let text_code = "from typing import overload
+class Reader:
+    @overload
+    def read(self, size: int) -> bytes: ...
+    @typing.overload
+    def read(self, size: None = None) -> str:
+        \"\"\"Variant docstring\"\"\"
+    def read(self, size=None):
+        \"\"\"Read from the stream.\"\"\"
+        return self._read(size)
+
+    def close(self):
+        pass
+
+@overload
+def load(path: str) -> dict: ...
+@overload
+def load(path: bytes) -> list: ...
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        assert_eq!(module.children.len(), 3);
+        let class = module.children[1].borrow();
+        assert_eq!(class.children.len(), 2);
+        let read = class.children[0].borrow();
+        assert_eq!(read.name, "def read");
+        assert_eq!(read.location(), Some((7, 9)));
+        assert_eq!(read.docstring().as_deref(), Some("Read from the stream."));
+        let overloads = read
+            .overloads
+            .iter()
+            .map(|o| o.render())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            overloads,
+            vec!["(self, size: int) -> bytes", "(self, size: None = None) -> str"]
+        );
+        assert_eq!(read.signature.as_ref().unwrap().render(), "(self, size=None)");
+
+        let load = module.children[2].borrow();
+        assert_eq!(load.name, "def load");
+        assert_eq!(load.overloads.len(), 2);
+        assert!(load.signature.is_none());
+        assert!(load.decorators.is_empty());
+    }
+
+    #[test]
+    fn test_parse_module_properties() {
+        let text_code = "class Account:
+    @property
+    def balance(self) -> Decimal:
+        \"\"\"Current balance.\"\"\"
+        return self._balance
+
+    @balance.setter
+    def balance(self, value):
+        \"\"\"Ignored setter docstring.\"\"\"
+        self._balance = value
+
+    @balance.deleter
+    def balance(self):
+        del self._balance
+
+    @functools.cached_property
+    def owner(self):
+        return self._load_owner()
+
+    def close(self):
+        pass
+"
+        .split('\n')
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>();
+        let mut processor = ContextProcessor::load(text_code);
+
+        let module = processor.parse_module();
+
+        let module = module.borrow();
+        let class = module.children[0].borrow();
+        let names = class
+            .children
+            .iter()
+            .map(|c| (c.borrow().name.clone(), c.borrow().context_type.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                (String::from("property balance"), definitions::ContextType::PROPERTY),
+                (String::from("property owner"), definitions::ContextType::PROPERTY),
+                (String::from("def close"), definitions::ContextType::METHOD),
+            ]
+        );
+
+        let balance = class.children[0].borrow();
+        assert_eq!(balance.docstring().as_deref(), Some("Current balance."));
+        assert_eq!(
+            balance.property,
+            Some(syntax::Property {
+                readable: true,
+                writable: true,
+                deletable: true,
+                annotation: Some(String::from("Decimal")),
+                cached: false,
+            })
+        );
+
+        let owner = class.children[1].borrow();
+        let owner = owner.property.as_ref().unwrap();
+        assert!(owner.readable && !owner.writable && owner.cached);
+    }
+
     #[test]
     fn test_indent_struct() {
         let mut new_indent = Indent::new();
@@ -0,0 +1,66 @@
+use super::definitions::ContextType;
+
+/// Structured event emitted by the processor while walking through a module.
+///
+/// Lines are zero based and depth counts the class/method contexts the
+/// event happened in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEvent {
+    /// A new context was entered
+    Enter {
+        context_type: ContextType,
+        name: String,
+        line: usize,
+        depth: usize,
+    },
+
+    /// The current context was closed
+    Exit {
+        context_type: ContextType,
+        name: String,
+        line: usize,
+        depth: usize,
+    },
+
+    /// Something in the module could not be processed as expected
+    Diagnostic { line: usize, message: String },
+}
+
+/// Receives the events of a `ContextProcessor`.
+///
+/// Closures taking a `&ParseEvent` can be used as observers directly.
+pub trait ParseObserver {
+    fn on_event(&mut self, event: &ParseEvent);
+}
+
+impl<F: FnMut(&ParseEvent)> ParseObserver for F {
+    fn on_event(&mut self, event: &ParseEvent) {
+        self(event)
+    }
+}
+
+/// Default observer, ignores every event.
+pub struct SilentObserver;
+
+impl ParseObserver for SilentObserver {
+    fn on_event(&mut self, _event: &ParseEvent) {}
+}
+
+/// Writes a readable trace of the events to stderr.
+pub struct TraceObserver;
+
+impl ParseObserver for TraceObserver {
+    fn on_event(&mut self, event: &ParseEvent) {
+        match event {
+            ParseEvent::Enter { context_type, line, depth, .. } => {
+                eprintln!("{}START -> [{:?}]. line:{}", "    ".repeat(*depth), context_type, line)
+            }
+            ParseEvent::Exit { context_type, line, depth, .. } => {
+                eprintln!("{}EXIT -> [{:?}]. line:{}", "    ".repeat(*depth), context_type, line)
+            }
+            ParseEvent::Diagnostic { line, message } => {
+                eprintln!("WARNING -> {}. line:{}", message, line)
+            }
+        }
+    }
+}
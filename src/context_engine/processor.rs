@@ -1,14 +1,28 @@
 use crate::context_engine::definitions::ContextNode;
 use regex::Regex;
 use std::cell::RefCell;
-use std::collections::HashMap;
 use std::rc::Rc;
 
+use super::custom::CustomContext;
 use super::definitions;
-use super::definitions::Indent;
+use super::definitions::{Indent, Span};
+use super::observer::{ParseEvent, ParseObserver, SilentObserver};
 use super::rules::LogicContext;
 use super::state::ContextState;
-use super::{ALL_MATCH, CLASS_MATCH, DEF_MATCH, DOCSTRING_END, DOCSTRING_START};
+use super::syntax::{
+    is_data_class, resolve_enum_values, slot_names, string_value, strip_comments, Attribute,
+    AttributeKind, ClassHeader, Deprecation, EnumMember, FunctionSignature, Guard, GuardKind,
+    ImportStatement, Property, PropertyAccessor, Variable,
+};
+use super::{
+    ALL_MATCH, CLASS_MATCH, DEF_MATCH, DOCSTRING_END, DOCSTRING_START, IMPORT_MATCH,
+    INSTANCE_ATTRIBUTE_MATCH, TYPE_ALIAS_MATCH, TYPE_CHECKING_MATCH, VARIABLE_MATCH,
+};
+
+/// Index of the first entry pattern checked after the user registered
+/// contexts: these win over the built-in statements but not over class, def
+/// and `__all__` headers.
+const CUSTOM_STAGE: usize = 3;
 
 pub struct ContextProcessor {
     pub context_state: ContextState,
@@ -17,49 +31,132 @@ pub struct ContextProcessor {
     pub(super) rules: LogicContext,
     pub(super) max_height: usize,
     pub(super) indent: Indent,
-    pub(super) patterns: HashMap<definitions::ContextType, Regex>,
+
+    /// Entry patterns in priority order, the first match wins
+    pub(super) patterns: Vec<(definitions::ContextType, Regex)>,
+
+    /// End of a docstring, checked on every line inside one
+    docstring_end: Regex,
+
+    /// `if TYPE_CHECKING:` headers guarding imports
+    type_checking: Regex,
+
+    /// Byte offset of the first character of every line
+    pub(super) line_offsets: Vec<usize>,
+
+    /// Lines starting inside an open bracket, a triple quoted string or
+    /// after a backslash. Their indentation carries no meaning.
+    pub(super) continuations: Vec<bool>,
+
+    /// Receives enter/exit/diagnostic events, silent unless replaced
+    observer: Box<dyn ParseObserver>,
+
+    /// User registered contexts, checked in registration order
+    pub(super) custom_contexts: Vec<CustomContext>,
 }
 
 impl ContextProcessor {
     pub fn load(file_lines: Vec<String>) -> Self {
-        let mut patterns: HashMap<definitions::ContextType, Regex> = HashMap::new();
-        patterns.insert(
-            definitions::ContextType::METHOD,
-            Regex::new(DEF_MATCH).unwrap(),
-        );
-        patterns.insert(
-            definitions::ContextType::CLASS,
-            Regex::new(CLASS_MATCH).unwrap(),
-        );
-        patterns.insert(
-            definitions::ContextType::ALL,
-            Regex::new(ALL_MATCH).unwrap(),
-        );
-        patterns.insert(
-            definitions::ContextType::DOCSTRING,
-            Regex::new(DOCSTRING_START).unwrap(),
-        );
+        // NOTE: The order of this vector is the matching priority. User
+        // registered contexts are checked right before `CUSTOM_STAGE`.
+        let patterns = vec![
+            (definitions::ContextType::CLASS, Regex::new(CLASS_MATCH).unwrap()),
+            (definitions::ContextType::METHOD, Regex::new(DEF_MATCH).unwrap()),
+            (definitions::ContextType::ALL, Regex::new(ALL_MATCH).unwrap()),
+            (definitions::ContextType::IMPORT, Regex::new(IMPORT_MATCH).unwrap()),
+            (definitions::ContextType::ALIAS, Regex::new(TYPE_ALIAS_MATCH).unwrap()),
+            (definitions::ContextType::VARIABLE, Regex::new(VARIABLE_MATCH).unwrap()),
+            (definitions::ContextType::ATTRIBUTE, Regex::new(VARIABLE_MATCH).unwrap()),
+            (definitions::ContextType::ATTRIBUTE, Regex::new(INSTANCE_ATTRIBUTE_MATCH).unwrap()),
+            (definitions::ContextType::DOCSTRING, Regex::new(DOCSTRING_START).unwrap()),
+        ];
+
+        let mut line_offsets = Vec::with_capacity(file_lines.len());
+        let mut offset = 0;
+        for line in &file_lines {
+            line_offsets.push(offset);
+            offset += line.len() + 1;
+        }
 
         Self {
             context_state: ContextState::new(),
             line_counter: 0,
             max_height: file_lines.len(),
+            continuations: find_continuations(&file_lines),
             file_lines,
             patterns,
+            docstring_end: Regex::new(DOCSTRING_END).unwrap(),
+            type_checking: Regex::new(TYPE_CHECKING_MATCH).unwrap(),
             indent: Indent::new(),
-            rules: LogicContext::new()
+            rules: LogicContext::new(),
+            line_offsets,
+            observer: Box::new(SilentObserver),
+            custom_contexts: Vec::new(),
         }
     }
 
+    /// Registers a user defined context, recorded as a leaf node wherever one
+    /// of its statements is found.
+    pub fn register_context(mut self, context: CustomContext) -> Self {
+        self.custom_contexts.push(context);
+        self
+    }
+
+    /// Replaces the rules used when walking through the module.
+    pub fn with_rules(mut self, rules: LogicContext) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Replaces the observer receiving the parse events.
+    pub fn with_observer(mut self, observer: impl ParseObserver + 'static) -> Self {
+        self.observer = Box::new(observer);
+        self
+    }
+
+    fn emit(&mut self, event: ParseEvent) {
+        self.observer.on_event(&event);
+    }
+
+    fn emit_context_event(&mut self, enter: bool) {
+        let (context_type, name) = {
+            let node = self.context_state.context_node.borrow();
+            (node.context_type.clone(), node.name.clone())
+        };
+        let line = self.line_counter;
+        let depth = self.indent.value().len() / 4;
+        let event = if enter {
+            ParseEvent::Enter { context_type, name, line, depth }
+        } else {
+            ParseEvent::Exit { context_type, name, line, depth }
+        };
+        self.emit(event);
+    }
+
+    fn pattern(&self, context_type: &definitions::ContextType) -> Option<&Regex> {
+        self.patterns
+            .iter()
+            .find(|(c, _)| c == context_type)
+            .map(|(_, pattern)| pattern)
+    }
+
     /// Checks for context entry point.
     ///
     /// If a line matches a python definition such as a class/method/all it
     /// returns the specific context.
     pub(super) fn check_context_entry(
         &self,
-        current_line: &String,
+        current_line: &str,
     ) -> Option<definitions::ContextType> {
-        for (context_type, pattern) in &self.patterns {
+        for (index, (context_type, pattern)) in self.patterns.iter().enumerate() {
+            if index == CUSTOM_STAGE {
+                if let Some(custom) = self.check_custom_entry(current_line) {
+                    return Some(custom);
+                }
+            }
+            if !self.allowed_in_current_context(context_type) {
+                continue;
+            }
             if pattern.is_match(current_line) {
                 if self.rules.contains(&self.context_state.context_type)
                     && self.rules.contains(context_type)
@@ -73,22 +170,710 @@ impl ContextProcessor {
         None
     }
 
+    /// Checks the user registered contexts, in registration order.
+    fn check_custom_entry(&self, current_line: &str) -> Option<definitions::ContextType> {
+        if self.custom_contexts.is_empty() || self.rules.contains(&self.context_state.context_type) {
+            return None;
+        }
+        let (statement, _) = self.statement(self.line_counter, current_line);
+        self.custom_contexts
+            .iter()
+            .find(|context| context.matches(&statement))
+            .map(CustomContext::context_type)
+    }
+
+    /// Checks if a context can be opened inside the current context.
+    fn allowed_in_current_context(&self, context_type: &definitions::ContextType) -> bool {
+        match context_type {
+            definitions::ContextType::VARIABLE | definitions::ContextType::ALIAS => {
+                self.context_state.context_type == definitions::ContextType::ROOT
+            }
+            definitions::ContextType::ATTRIBUTE => {
+                self.context_state.context_type == definitions::ContextType::CLASS
+                    || self.in_initializer()
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns the statement starting on the given line together with the
+    /// index of its last line.
+    ///
+    /// Continuation lines are joined with newlines. Decorators are followed
+    /// by the lines up to the header of the decorated definition.
+    fn statement(&self, line: usize, current_line: &str) -> (String, usize) {
+        let mut statement = current_line.to_string();
+        let mut last = line;
+        let mut decorated = current_line.trim_start().starts_with('@');
+        while let Some(next) = self.file_lines.get(last + 1) {
+            let continues = self.continuations.get(last + 1).copied().unwrap_or(false);
+            if !continues && !decorated {
+                break;
+            }
+            if !continues {
+                decorated = next.trim_start().starts_with('@');
+            }
+            statement.push('\n');
+            statement.push_str(next);
+            last += 1;
+        }
+        (statement, last)
+    }
+
+    /// Records a user defined context as a leaf child of the current node.
+    fn record_custom_context(&mut self, context_type: definitions::ContextType, current_line: &str) {
+        let (statement, last) = self.statement(self.line_counter, current_line);
+        let context = match self
+            .custom_contexts
+            .iter()
+            .find(|context| context.context_type() == context_type)
+        {
+            Some(context) => context,
+            None => return,
+        };
+        let name = context.name(&statement).unwrap_or_default();
+        let value = context.value(&statement);
+
+        let node = self.record_leaf(name, context_type, current_line, last);
+        node.borrow_mut().value = value;
+    }
+
+    /// Records an import statement as a leaf child of the current node.
+    fn record_import(&mut self, current_line: &str) {
+        let (statement, last) = self.statement(self.line_counter, current_line);
+        let mut import = match ImportStatement::parse(&statement) {
+            Some(import) => import,
+            None => return,
+        };
+        for (header_line, header) in self.enclosing_headers(self.line_counter) {
+            let header = header.trim();
+            if self.type_checking.is_match(header) {
+                import.type_checking = true;
+            }
+            if header.starts_with("except") && catches_import_error(header) {
+                import.import_error_guard = true;
+            }
+            if header.starts_with("try") && self.try_catches_import_error(header_line) {
+                import.import_error_guard = true;
+            }
+        }
+
+        let name = import.source_module();
+        let node = self.record_leaf(name, definitions::ContextType::IMPORT, current_line, last);
+        node.borrow_mut().import = Some(import);
+    }
+
+    /// Records an assignment or `type` statement as a leaf child of the
+    /// current node.
+    fn record_variable(&mut self, current_line: &str) {
+        let (statement, last) = self.statement(self.line_counter, current_line);
+        let (name, variable) = match Variable::parse(&statement) {
+            Some(parsed) => parsed,
+            None => return,
+        };
+        let context_type = if variable.is_alias() {
+            definitions::ContextType::ALIAS
+        } else {
+            definitions::ContextType::VARIABLE
+        };
+        let node = self.record_leaf(name, context_type, current_line, last);
+        node.borrow_mut().variable = Some(variable);
+    }
+
+    /// Records the `__all__` assignment as a leaf holding the assigned list.
+    fn record_all(&mut self, current_line: &str) {
+        let (statement, last) = self.statement(self.line_counter, current_line);
+        let variable = Variable::parse(&statement).map(|(_, variable)| variable);
+        let node = self.record_leaf(
+            String::from("__all__"),
+            definitions::ContextType::ALL,
+            current_line,
+            last,
+        );
+        node.borrow_mut().variable = variable;
+    }
+
+    /// Checks if the current context is the `__init__` method of a class.
+    fn in_initializer(&self) -> bool {
+        let node = self.context_state.context_node.borrow();
+        node.context_type == definitions::ContextType::METHOD
+            && node.identifier() == "__init__"
+            && node
+                .parent
+                .as_ref()
+                .is_some_and(|p| p.borrow().context_type == definitions::ContextType::CLASS)
+    }
+
+    /// Records class level assignments, `__slots__` entries and `self`
+    /// assignments inside `__init__` as attribute leaves.
+    fn record_attribute(&mut self, current_line: &str) {
+        let (statement, last) = self.statement(self.line_counter, current_line);
+        let in_initializer = self.in_initializer();
+        let statement = match (in_initializer, statement.trim_start().strip_prefix("self.")) {
+            (true, Some(statement)) => statement.to_string(),
+            (true, None) => return,
+            (false, _) => statement,
+        };
+        let (name, variable) = match Variable::parse(&statement) {
+            Some(parsed) if !parsed.1.type_statement => parsed,
+            _ => return,
+        };
+
+        if in_initializer {
+            let attribute = Attribute::new(AttributeKind::Instance);
+            let node = self.record_leaf(name, definitions::ContextType::ATTRIBUTE, current_line, last);
+            node.borrow_mut().variable = Some(variable);
+            node.borrow_mut().attribute = Some(attribute);
+            return;
+        }
+
+        if name == "__slots__" {
+            for (slot, doc) in slot_names(variable.value.as_deref().unwrap_or_default()) {
+                let node = self.record_leaf(slot, definitions::ContextType::ATTRIBUTE, current_line, last);
+                node.borrow_mut().variable = Some(Variable::default());
+                node.borrow_mut().attribute = Some(Attribute::new(AttributeKind::Slot));
+                if let Some(doc) = doc {
+                    let docstring = definitions::ContextNode::new(
+                        String::from("__empty__"),
+                        definitions::ContextType::DOCSTRING,
+                        self.line_counter,
+                        true,
+                    );
+                    docstring.borrow_mut().value = Some(doc);
+                    docstring.borrow_mut().set_parent(Rc::clone(&node));
+                    node.borrow_mut().add_node(docstring);
+                }
+            }
+            return;
+        }
+
+        let class_var = variable
+            .annotation
+            .as_deref()
+            .is_some_and(|a| a.starts_with("ClassVar") || a.starts_with("typing.ClassVar"));
+        let data_class = {
+            let class_node = self.context_state.context_node.borrow();
+            is_data_class(&class_node.decorators, class_node.class_header.as_ref())
+        };
+        let attribute = if data_class && !class_var && variable.annotation.is_some() {
+            Attribute::field(variable.value.as_deref())
+        } else {
+            let mut attribute = Attribute::new(AttributeKind::Class);
+            attribute.default = variable.value.clone();
+            attribute
+        };
+        let node = self.record_leaf(name, definitions::ContextType::ATTRIBUTE, current_line, last);
+        node.borrow_mut().variable = Some(variable);
+        node.borrow_mut().attribute = Some(attribute);
+    }
+
+    /// Moves the instance attributes recorded inside `__init__` to the class,
+    /// skipping the ones the class already declares.
+    fn hoist_instance_attributes(&self, node: &Rc<RefCell<ContextNode>>) {
+        let children = node.borrow().children.clone();
+        for child in &children {
+            self.hoist_instance_attributes(child);
+        }
+        if node.borrow().context_type != definitions::ContextType::CLASS {
+            return;
+        }
+
+        for method in children.iter().filter(|c| c.borrow().identifier() == "__init__") {
+            let attributes = method
+                .borrow()
+                .children
+                .iter()
+                .filter(|c| c.borrow().context_type == definitions::ContextType::ATTRIBUTE)
+                .map(Rc::clone)
+                .collect::<Vec<_>>();
+            method
+                .borrow_mut()
+                .children
+                .retain(|c| c.borrow().context_type != definitions::ContextType::ATTRIBUTE);
+
+            for attribute in attributes {
+                let declared = node
+                    .borrow()
+                    .children
+                    .iter()
+                    .any(|c| {
+                        let c = c.borrow();
+                        c.context_type == definitions::ContextType::ATTRIBUTE
+                            && c.name == attribute.borrow().name
+                    });
+                if !declared {
+                    attribute.borrow_mut().set_parent(Rc::clone(node));
+                    node.borrow_mut().add_node(attribute);
+                }
+            }
+        }
+    }
+
+    /// Lists the members of enum classes: class attributes assigned a value,
+    /// without annotation and not using a `_sunder_` or `__dunder__` name.
+    fn collect_enum_members(&self, node: &Rc<RefCell<ContextNode>>) {
+        for child in node.borrow().children.iter() {
+            self.collect_enum_members(child);
+        }
+        let base = match node.borrow().class_header.as_ref().and_then(|h| h.enum_base()) {
+            Some(base) => base.to_string(),
+            None => return,
+        };
+
+        let mut members = Vec::new();
+        for child in node.borrow().children.iter() {
+            let child = child.borrow();
+            let variable = match (&child.context_type, &child.variable) {
+                (definitions::ContextType::ATTRIBUTE, Some(variable)) => variable,
+                _ => continue,
+            };
+            let value = match &variable.value {
+                Some(value) if variable.annotation.is_none() => value,
+                _ => continue,
+            };
+            if child.name.starts_with('_') || value.starts_with("nonmember(") {
+                continue;
+            }
+            let line = child.span.start_line.unwrap_or(0);
+            members.push(EnumMember {
+                name: child.name.clone(),
+                value: value.clone(),
+                resolved_value: None,
+                docstring: child.docstring(),
+                comment: self.member_comment(line),
+                line,
+            });
+        }
+        resolve_enum_values(&base, &mut members);
+        node.borrow_mut().enum_members = Some(members);
+    }
+
+    /// Reads the comment after a statement or, failing that, the comment
+    /// lines right above it.
+    fn member_comment(&self, line: usize) -> Option<String> {
+        let text = self.file_lines.get(line)?;
+        let code = strip_comments(text);
+        if let Some(comment) = text[code.len()..].trim().strip_prefix('#') {
+            return Some(comment.trim().to_string());
+        }
+
+        let comments = (0..line)
+            .rev()
+            .map(|index| self.file_lines[index].trim())
+            .take_while(|text| text.starts_with('#'))
+            .map(|text| text.trim_start_matches(['#', ':']).trim())
+            .collect::<Vec<&str>>();
+        if comments.is_empty() {
+            return None;
+        }
+        Some(comments.into_iter().rev().collect::<Vec<&str>>().join("\n"))
+    }
+
+    /// Merges the `@overload` variants of a function into its implementation.
+    /// Without implementation, as in stubs and protocols, the first variant
+    /// holds the group and has no signature of its own.
+    fn group_overloads(&self, node: &Rc<RefCell<ContextNode>>) {
+        let children = node.borrow().children.clone();
+        let mut kept: Vec<Rc<RefCell<ContextNode>>> = Vec::with_capacity(children.len());
+        let mut variants: Vec<Rc<RefCell<ContextNode>>> = Vec::new();
+
+        for child in children {
+            self.group_overloads(&child);
+            let (is_method, is_overload, name) = {
+                let child = child.borrow();
+                (
+                    child.context_type == definitions::ContextType::METHOD,
+                    child
+                        .decorators
+                        .iter()
+                        .any(|d| d.rsplit('.').next() == Some("overload")),
+                    child.name.clone(),
+                )
+            };
+            if variants.first().is_some_and(|v| v.borrow().name != name) {
+                kept.push(merge_overloads(None, &variants));
+                variants.clear();
+            }
+            if is_method && is_overload {
+                variants.push(child);
+                continue;
+            }
+            if is_method && !variants.is_empty() {
+                kept.push(merge_overloads(Some(child), &variants));
+                variants.clear();
+                continue;
+            }
+            kept.push(child);
+        }
+        if !variants.is_empty() {
+            kept.push(merge_overloads(None, &variants));
+        }
+        node.borrow_mut().children = kept;
+    }
+
+    /// Turns the `@property` getters of classes into PROPERTY nodes and merges
+    /// the setters and deleters defined after them into those nodes. The
+    /// getter provides the docstring and type.
+    fn group_properties(&self, node: &Rc<RefCell<ContextNode>>) {
+        let children = node.borrow().children.clone();
+        if node.borrow().context_type != definitions::ContextType::CLASS {
+            for child in children.iter() {
+                self.group_properties(child);
+            }
+            return;
+        }
+
+        let mut kept: Vec<Rc<RefCell<ContextNode>>> = Vec::with_capacity(children.len());
+        for child in children {
+            self.group_properties(&child);
+            let accessor = {
+                let child = child.borrow();
+                let name = child.identifier();
+                match child.context_type {
+                    definitions::ContextType::METHOD => child
+                        .decorators
+                        .iter()
+                        .find_map(|decorator| Property::accessor(decorator, name)),
+                    _ => None,
+                }
+            };
+            let property = kept.iter().rev().find(|p| {
+                let p = p.borrow();
+                p.context_type == definitions::ContextType::PROPERTY
+                    && p.identifier() == child.borrow().identifier()
+            });
+
+            match (accessor, property) {
+                (Some(PropertyAccessor::Getter | PropertyAccessor::Cached), None) => {
+                    let mut getter = child.borrow_mut();
+                    getter.name = format!("property {}", getter.identifier());
+                    getter.context_type = definitions::ContextType::PROPERTY;
+                    getter.property = Some(Property {
+                        readable: true,
+                        annotation: getter
+                            .signature
+                            .as_ref()
+                            .and_then(|s| s.return_annotation.clone()),
+                        cached: accessor == Some(PropertyAccessor::Cached),
+                        ..Property::default()
+                    });
+                }
+                (Some(accessor), Some(property)) => {
+                    let mut property = property.borrow_mut();
+                    let details = property.property.get_or_insert_with(Property::default);
+                    match accessor {
+                        PropertyAccessor::Setter => details.writable = true,
+                        PropertyAccessor::Deleter => details.deletable = true,
+                        _ => details.readable = true,
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+            kept.push(child);
+        }
+        node.borrow_mut().children = kept;
+    }
+
+    /// Records the deprecations declared by decorators, by warnings emitted at
+    /// the top of function bodies and by docstring directives, in that order
+    /// of precedence.
+    fn detect_deprecations(&self, node: &Rc<RefCell<ContextNode>>) {
+        for child in node.borrow().children.iter() {
+            self.detect_deprecations(child);
+        }
+
+        let mut node = node.borrow_mut();
+        let decorator = node.decorators.iter().find_map(|d| Deprecation::from_decorator(d));
+        let warning = match node.context_type {
+            definitions::ContextType::METHOD | definitions::ContextType::PROPERTY => {
+                self.deprecation_warning(&node)
+            }
+            _ => None,
+        };
+        let directive = node.docstring().and_then(|d| Deprecation::from_docstring(&d));
+        node.deprecation = [decorator, warning, directive]
+            .into_iter()
+            .flatten()
+            .reduce(Deprecation::merge);
+    }
+
+    /// Finds a deprecation warning among the first statements of a function
+    /// body, skipping its docstring and imports.
+    fn deprecation_warning(&self, node: &ContextNode) -> Option<Deprecation> {
+        let (start, end) = node.body_span.lines()?;
+        if node.signature_span.end_line == Some(start) {
+            return None;
+        }
+        let mut line = start;
+        while line <= end {
+            let text = self.file_lines.get(line)?;
+            if !self.is_code_line(line, text) {
+                line += 1;
+                continue;
+            }
+            let (statement, last) = self.statement(line, text);
+            let statement = statement.trim();
+            let skipped = string_value(statement).is_some()
+                || statement.starts_with("import ")
+                || statement.starts_with("from ");
+            if !skipped {
+                return Deprecation::from_warning(statement);
+            }
+            line = last + 1;
+        }
+        None
+    }
+
+    /// Reads the decorators written above a definition header, in source
+    /// order and without the leading `@`.
+    fn decorators_above(&self, line: usize) -> Vec<String> {
+        let mut decorators = Vec::new();
+        let mut pending: Vec<&str> = Vec::new();
+        for index in (0..line).rev() {
+            let text = self.file_lines[index].trim();
+            if text.starts_with('#') {
+                continue;
+            }
+            pending.push(text);
+            if self.continuations[index] {
+                continue;
+            }
+            if !text.starts_with('@') {
+                break;
+            }
+            pending.reverse();
+            let decorator = pending.concat();
+            decorators.push(decorator.trim_start_matches('@').trim().to_string());
+            pending.clear();
+        }
+        decorators.reverse();
+        decorators
+    }
+
+    /// Moves the string literal directly following a variable into the
+    /// children of the variable, as its attribute docstring.
+    fn attach_attribute_docstrings(&self, node: &Rc<RefCell<ContextNode>>) {
+        let children = node.borrow().children.clone();
+        let mut kept: Vec<Rc<RefCell<ContextNode>>> = Vec::with_capacity(children.len());
+
+        for child in children {
+            self.attach_attribute_docstrings(&child);
+            let follows_variable = kept.last().is_some_and(|previous| {
+                let previous = previous.borrow();
+                previous.variable.is_some()
+                    && previous.span.end_line.map(|end| end + 1) == child.borrow().span.start_line
+            });
+            if follows_variable && child.borrow().context_type == definitions::ContextType::DOCSTRING {
+                let holder = kept.last().unwrap();
+                child.borrow_mut().set_parent(Rc::clone(holder));
+                holder.borrow_mut().add_node(child);
+                continue;
+            }
+            kept.push(child);
+        }
+
+        for holder in kept.iter().filter(|c| c.borrow().variable.is_some()) {
+            let next_line = holder.borrow().span.end_line.map(|end| end + 1);
+            if let Some(docstring) = next_line.and_then(|line| self.string_statement(line, holder)) {
+                holder.borrow_mut().add_node(docstring);
+            }
+        }
+        node.borrow_mut().children = kept;
+    }
+
+    /// Creates a docstring node for a single line string literal statement,
+    /// which triple quoted docstrings patterns do not catch.
+    fn string_statement(&self, line: usize, parent: &Rc<RefCell<ContextNode>>) -> Option<Rc<RefCell<ContextNode>>> {
+        let text = self.file_lines.get(line)?;
+        let literal = text.trim();
+        let quote = literal.trim_start_matches(['r', 'R', 'u', 'U']).chars().next()?;
+        if !(quote == '"' || quote == '\'') || literal.len() < 2 || !literal.ends_with(quote) {
+            return None;
+        }
+        if literal.ends_with("\"\"\"") || literal.ends_with("'''") {
+            return None;
+        }
+        if parent.borrow().children.iter().any(|c| c.borrow().context_type == definitions::ContextType::DOCSTRING) {
+            return None;
+        }
+
+        let node = definitions::ContextNode::new(
+            String::from("__empty__"),
+            definitions::ContextType::DOCSTRING,
+            line,
+            true,
+        );
+        {
+            let mut node = node.borrow_mut();
+            node.value = Some(text.to_string());
+            let (l, c, o) = self.point(line, text, indentation(text));
+            node.span.set_start(l, c, o);
+            let (l, c, o) = self.point(line, text, text.trim_end().len());
+            node.span.set_end(l, c, o);
+            node.set_parent(Rc::clone(parent));
+        }
+        Some(node)
+    }
+
+    /// Checks the `except` clauses following a `try:` header.
+    fn try_catches_import_error(&self, try_line: usize) -> bool {
+        let try_indent = indentation(&self.file_lines[try_line]);
+        for index in try_line + 1..self.max_height {
+            let line = &self.file_lines[index];
+            if !self.is_code_line(index, line) {
+                continue;
+            }
+            if indentation(line) < try_indent {
+                break;
+            }
+            if indentation(line) == try_indent {
+                let line = line.trim_start();
+                if !line.starts_with("except") {
+                    break;
+                }
+                if catches_import_error(line) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Lists the block headers enclosing a line, innermost first. Headers are
+    /// found by walking upwards to the lines holding code with a smaller
+    /// indentation.
+    pub(super) fn enclosing_headers(&self, line: usize) -> Vec<(usize, &str)> {
+        let mut headers = Vec::new();
+        let mut current_indent = match self.file_lines.get(line) {
+            Some(text) => indentation(text),
+            None => return headers,
+        };
+        for index in (0..line).rev() {
+            if current_indent == 0 {
+                break;
+            }
+            let text = &self.file_lines[index];
+            if self.is_code_line(index, text) && indentation(text) < current_indent {
+                current_indent = indentation(text);
+                headers.push((index, text.as_str()));
+            }
+        }
+        headers
+    }
+
+    /// Lists the control flow blocks enclosing a line up to the closest class
+    /// or def header, outermost first.
+    pub(super) fn guards(&self, line: usize) -> Vec<Guard> {
+        let mut guards = Vec::new();
+        for (header_line, header) in self.enclosing_headers(line) {
+            let (statement, _) = self.statement(header_line, header);
+            let mut guard = match Guard::parse(&statement, header_line) {
+                Some(guard) => guard,
+                None => break,
+            };
+            if matches!(guard.kind, GuardKind::Elif | GuardKind::Else) {
+                guard.previous_conditions = self.previous_conditions(header_line);
+            }
+            guards.push(guard);
+        }
+        guards.reverse();
+        guards
+    }
+
+    /// Collects the tests of the `if`/`elif` branches preceding the branch
+    /// opened on the given line.
+    fn previous_conditions(&self, branch_line: usize) -> Vec<String> {
+        let branch_indent = indentation(&self.file_lines[branch_line]);
+        let mut conditions = Vec::new();
+        for index in (0..branch_line).rev() {
+            let text = &self.file_lines[index];
+            if !self.is_code_line(index, text) || indentation(text) > branch_indent {
+                continue;
+            }
+            if indentation(text) < branch_indent {
+                break;
+            }
+            let (statement, _) = self.statement(index, text);
+            match Guard::parse(&statement, index) {
+                Some(Guard { kind: GuardKind::Elif, condition: Some(condition), .. }) => {
+                    conditions.push(condition)
+                }
+                Some(Guard { kind: GuardKind::If, condition: Some(condition), .. }) => {
+                    conditions.push(condition);
+                    break;
+                }
+                _ => break,
+            }
+        }
+        conditions.reverse();
+        conditions
+    }
+
+    /// Creates a leaf node for the statement between the current line and
+    /// `last` and adds it to the children of the current node.
+    fn record_leaf(
+        &mut self,
+        name: String,
+        context_type: definitions::ContextType,
+        current_line: &str,
+        last: usize,
+    ) -> Rc<RefCell<ContextNode>> {
+        let is_public = !name.starts_with('_') || (name.starts_with("__") && name.ends_with("__"));
+        let node = definitions::ContextNode::new(name, context_type, self.line_counter, is_public);
+        {
+            let mut node = node.borrow_mut();
+            node.guards = self.guards(self.line_counter);
+            let column = indentation(current_line);
+            let (l, c, o) = self.point(self.line_counter, current_line, column);
+            node.span.set_start(l, c, o);
+            let end_text = self.line_text(last, current_line);
+            let (l, c, o) = self.point(last, end_text, end_text.trim_end().len());
+            node.span.set_end(l, c, o);
+            if let Some(index) = (!node.name.is_empty())
+                .then(|| current_line.find(node.name.as_str()))
+                .flatten()
+            {
+                let (l, c, o) = self.point(self.line_counter, current_line, index);
+                node.name_span.set_start(l, c, o);
+                let (l, c, o) = self.point(self.line_counter, current_line, index + node.name.len());
+                node.name_span.set_end(l, c, o);
+            }
+        }
+        let parent = Rc::clone(&self.context_state.context_node);
+        node.borrow_mut().set_parent(Rc::clone(&parent));
+        parent.borrow_mut().add_node(Rc::clone(&node));
+        node
+    }
+
     /// Changes the state of the processor so that it reflects being inside a
     /// context.
     pub(super) fn start_context(
         &mut self,
         context_type: definitions::ContextType,
-        current_line: &String,
+        current_line: &str,
     ) {
         self.context_state.context_type = context_type.clone();
         let context_name = self.get_context_name(current_line);
-        let is_public = !context_name.starts_with("_");
+        let identifier = context_name.rsplit(' ').next().unwrap_or_default();
+        let is_public = !identifier.starts_with('_')
+            || (identifier.starts_with("__") && identifier.ends_with("__"));
         let child_node = definitions::ContextNode::new(
             context_name,
-            context_type.clone(),
+            context_type,
             self.line_counter,
             is_public,
         );
+        if self.line_counter < self.max_height {
+            child_node.borrow_mut().decorators = self.decorators_above(self.line_counter);
+            child_node.borrow_mut().guards = self.guards(self.line_counter);
+        }
+        if !self.set_start_spans(&mut child_node.borrow_mut(), current_line) {
+            self.emit(ParseEvent::Diagnostic {
+                line: self.line_counter,
+                message: String::from("definition header is not closed by a colon"),
+            });
+        }
         self.context_state.descend(child_node);
         if !self.rules.contains(&self.context_state.context_type) {
             self.indent.increase();
@@ -96,141 +881,155 @@ impl ContextProcessor {
     }
 
     /// Extracts context name based on context type
-    pub(super) fn get_context_name(&self, current_line: &String) -> String {
-        match self.context_state.context_type {
-            definitions::ContextType::METHOD => {
-                let c = self
-                    .patterns
-                    .get(&definitions::ContextType::METHOD)
-                    .unwrap()
-                    .captures(current_line)
-                    .unwrap();
-                c.get(0).unwrap().as_str().to_string()
-            }
-            definitions::ContextType::CLASS => {
-                let pattern = self.patterns.get(&definitions::ContextType::CLASS).unwrap();
-                let c = pattern.captures(current_line).unwrap();
-                c.get(0).unwrap().as_str().to_string()
-            }
-            _ => String::from("__empty__"),
-        }
+    pub(super) fn get_context_name(&self, current_line: &str) -> String {
+        let keyword = match self.context_state.context_type {
+            definitions::ContextType::METHOD => "def",
+            definitions::ContextType::CLASS => "class",
+            _ => return String::from("__empty__"),
+        };
+        let c = self
+            .pattern(&self.context_state.context_type)
+            .unwrap()
+            .captures(current_line)
+            .unwrap();
+        format!("{} {}", keyword, &c["ident"])
     }
 
     /// Check if the current line represents an exit point from the
     /// current context.
-    pub(super) fn check_context_exit(&self, current_line: &String) -> bool {
-        let line_is_empty = |line: &String| line.is_empty() || !line.starts_with("    ");
-        let get_second_line = |line_counter: usize| &self.file_lines[line_counter + 1];
-
+    ///
+    /// Block contexts end on the last line before the code dedents to (or
+    /// below) the indentation of their header. A blank line is an exit point
+    /// if the next line holding code is outside the block.
+    pub(super) fn check_context_exit(&self, current_line: &str) -> bool {
         match self.context_state.context_type {
             definitions::ContextType::ROOT => false,
-            definitions::ContextType::DOCSTRING => {
-                let r = Regex::new(DOCSTRING_END).unwrap();
-                r.is_match(current_line)
-            }
-            definitions::ContextType::CLASS => {
-                if !current_line.is_empty() {
-                    return false;
-                }
-                if self.line_counter + 1 > self.max_height { true }
-                else if line_is_empty(&get_second_line(self.line_counter)) { true }
-                else { false }
-            }
+            definitions::ContextType::DOCSTRING => self.docstring_end.is_match(current_line),
             _ => {
-                if !current_line.is_empty() {
-                    return false;
+                let header_indent = self
+                    .context_state
+                    .context_node
+                    .borrow()
+                    .span
+                    .start_column
+                    .unwrap_or(0);
+                let next_line = if self.is_code_line(self.line_counter, current_line) {
+                    Some(current_line)
+                } else {
+                    self.next_code_line(self.line_counter + 1)
+                };
+
+                match next_line {
+                    Some(line) => indentation(line) <= header_indent,
+                    None => true,
                 }
-                let second_line = get_second_line(self.line_counter);
-                if self.line_counter + 1 > self.max_height { true }
-                else if line_is_empty(&second_line) { true }
-                else { false }
             }
         }
     }
 
     /// Changes the state of the processor so that it reflects being outside
     /// current context.
-    fn exit_context(&mut self) -> () {
-        self.indent.decrease();
+    fn exit_context(&mut self) {
+        self.emit_context_event(false);
+        if !self.rules.contains(&self.context_state.context_type) {
+            self.indent.decrease();
+        }
+        self.set_end_spans();
         self.context_state.ascend();
-        self.context_state
-            .context_node
-            .borrow_mut()
-            .set_location(self.line_counter);
     }
 
     /// Extracting lines that are used inside unique context types
     ///
     /// For example, docstring context would need the string values.
     /// Each new context can be safely added as a match arm to this method.
-    fn extract_context_data(&mut self, current_line: &String) {
-        match self.context_state.context_type {
-            definitions::ContextType::DOCSTRING => {
-                self.context_state
-                    .context_node
-                    .borrow_mut()
-                    .append_value(current_line);
+    fn extract_context_data(&mut self, current_line: &str) {
+        if self.context_state.context_type == definitions::ContextType::DOCSTRING {
+            let mut node = self.context_state.context_node.borrow_mut();
+            if node.value.is_some() {
+                node.append_value("\n");
             }
-            _ => (),
+            node.append_value(current_line);
         }
     }
 
-    fn check_root_exit(&mut self) -> bool {
-        if self.line_counter + 1 >= self.max_height {
-            return false;
-        }
-        let second_line = &self.file_lines[self.line_counter + 1];
-        if !second_line.starts_with(self.indent.value()) {
-            while self.context_state.context_type != definitions::ContextType::ROOT {
-                self.exit_context();
-            }
-            return true;
-        }
-        false
+    /// Checks if a docstring is closed on the same line it was opened.
+    fn docstring_closes_on_entry(&self, current_line: &str) -> bool {
+        let opening = match current_line.find("\"\"\"").or_else(|| current_line.find("'''")) {
+            Some(index) => index + 3,
+            None => return false,
+        };
+        self.docstring_end.is_match(&current_line[opening..])
     }
 
     pub fn parse_module(&mut self) -> Rc<RefCell<ContextNode>> {
-        let mut lines = self
-            .file_lines
-            .to_owned()
-            .into_iter()
-            .take(self.max_height - 1);
+        self.context_state
+            .context_node
+            .borrow_mut()
+            .span
+            .set_start(0, 0, Some(0));
 
-        loop {
-            // Fetch current line
-            let current_line = match lines.next() {
-                Some(line) => line,
-                None => {
-                    self.context_state.top();
-                    break;
+        let lines = self.file_lines.to_owned();
+        for current_line in lines.iter() {
+            // Logic contexts only collect values until they are closed
+            if self.rules.contains(&self.context_state.context_type) {
+                self.extract_context_data(current_line);
+                if self.check_context_exit(current_line) {
+                    self.exit_context();
                 }
-            };
-
-            // Check context entry
-            if let Some(c) = self.check_context_entry(&current_line) {
-                self.start_context(c, &current_line);
-                println!(
-                    "{}START -> [{:?}]. line:{}",
-                    self.indent.value(),
-                    self.context_state.context_type,
-                    self.line_counter
-                )
+                self.line_counter += 1;
+                continue;
             }
 
-            // Process any kind of context for values
-            self.extract_context_data(&current_line);
-
             // Check exit
+            while self.context_state.context_type != definitions::ContextType::ROOT
+                && self.check_context_exit(current_line)
+            {
+                self.exit_context();
+            }
 
-            if self.check_context_exit(&current_line) {
-                println!(
-                    "{}EXIT -> [{:?}]. line:{}",
-                    self.indent.value(),
-                    self.context_state.context_type,
-                    self.line_counter
-                );
-                if !self.check_root_exit() {
-                    self.exit_context();
+            // Check context entry
+            if self.continuations[self.line_counter] {
+                self.line_counter += 1;
+                continue;
+            }
+            if let Some(c) = self.check_context_entry(current_line) {
+                match c {
+                    definitions::ContextType::CUSTOM(_) => {
+                        self.record_custom_context(c, current_line);
+                        self.line_counter += 1;
+                        continue;
+                    }
+                    definitions::ContextType::IMPORT => {
+                        self.record_import(current_line);
+                        self.line_counter += 1;
+                        continue;
+                    }
+                    definitions::ContextType::VARIABLE | definitions::ContextType::ALIAS => {
+                        self.record_variable(current_line);
+                        self.line_counter += 1;
+                        continue;
+                    }
+                    definitions::ContextType::ATTRIBUTE => {
+                        self.record_attribute(current_line);
+                        self.line_counter += 1;
+                        continue;
+                    }
+                    definitions::ContextType::ALL => {
+                        self.record_all(current_line);
+                        self.line_counter += 1;
+                        continue;
+                    }
+                    _ => (),
+                }
+                self.start_context(c, current_line);
+                self.emit_context_event(true);
+
+                // Process any kind of context for values
+                if self.rules.contains(&self.context_state.context_type) {
+                    self.extract_context_data(current_line);
+                    if self.docstring_closes_on_entry(current_line) {
+                        self.exit_context();
+                    }
                 }
             }
 
@@ -238,6 +1037,306 @@ impl ContextProcessor {
             self.line_counter += 1;
         }
 
-        Rc::clone(&self.context_state.context_node)
+        if self.context_state.context_type == definitions::ContextType::DOCSTRING {
+            let line = self.context_state.context_node.borrow().span.start_line.unwrap_or(0);
+            self.emit(ParseEvent::Diagnostic {
+                line,
+                message: String::from("docstring is never closed"),
+            });
+        }
+        while self.context_state.context_type != definitions::ContextType::ROOT {
+            self.exit_context();
+        }
+        let root = Rc::clone(&self.context_state.context_node);
+        close_block_node(
+            &mut root.borrow_mut(),
+            &self.file_lines,
+            &self.line_offsets,
+            self.line_counter,
+        );
+        self.attach_attribute_docstrings(&root);
+        self.hoist_instance_attributes(&root);
+        self.collect_enum_members(&root);
+        self.group_overloads(&root);
+        self.group_properties(&root);
+        self.detect_deprecations(&root);
+        self.rules.prune(&root);
+        root
+    }
+
+    /// Returns line, character column and byte offset for a byte index
+    /// inside one of the module lines.
+    fn point(&self, line: usize, text: &str, index: usize) -> (usize, usize, Option<usize>) {
+        point(&self.line_offsets, line, text, index)
+    }
+
+    fn is_code_line(&self, line: usize, text: &str) -> bool {
+        let trimmed = text.trim_start();
+        !trimmed.is_empty()
+            && !trimmed.starts_with('#')
+            && !self.continuations.get(line).copied().unwrap_or(false)
+    }
+
+    fn next_code_line(&self, from: usize) -> Option<&str> {
+        (from..self.max_height)
+            .map(|i| (i, &self.file_lines[i]))
+            .find(|(i, line)| self.is_code_line(*i, line))
+            .map(|(_, line)| line.as_str())
+    }
+
+    /// Sets the start coordinates of a freshly created node. Class and method
+    /// nodes also get their name, signature and body start coordinates.
+    ///
+    /// Returns false if the header of a definition could not be delimited.
+    fn set_start_spans(&self, node: &mut ContextNode, current_line: &str) -> bool {
+        let line = self.line_counter;
+        let column = current_line.len() - current_line.trim_start().len();
+        let (l, c, o) = self.point(line, current_line, column);
+        node.span.set_start(l, c, o);
+
+        let ident = match self
+            .pattern(&node.context_type)
+            .and_then(|pattern| pattern.captures(current_line))
+            .and_then(|c| c.name("ident"))
+        {
+            Some(m) => m,
+            None => return true,
+        };
+
+        let (l, c, o) = self.point(line, current_line, ident.start());
+        node.name_span.set_start(l, c, o);
+        let (l, c, o) = self.point(line, current_line, ident.end());
+        node.name_span.set_end(l, c, o);
+
+        node.signature_span = node.span;
+        let (end_line, end_index) = match self.find_header_end(line, current_line, column) {
+            Some(end) => end,
+            None => return false,
+        };
+        let end_text = self.line_text(end_line, current_line);
+        let (l, c, o) = self.point(end_line, end_text, end_index);
+        node.signature_span.set_end(l, c, o);
+
+        let header = self.header_text(line, current_line, column, end_line, end_index);
+        match node.context_type {
+            definitions::ContextType::CLASS => node.class_header = Some(ClassHeader::parse(&header)),
+            definitions::ContextType::METHOD => node.signature = FunctionSignature::parse(&header),
+            _ => (),
+        }
+
+        let rest = end_text[end_index..].trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            let index = end_text.len() - rest.len();
+            let (l, c, o) = self.point(end_line, end_text, index);
+            node.body_span.set_start(l, c, o);
+        } else if let Some(body_line) =
+            (end_line + 1..self.max_height).find(|i| self.is_code_line(*i, &self.file_lines[*i]))
+        {
+            let text = &self.file_lines[body_line];
+            let (l, c, o) = self.point(body_line, text, indentation(text));
+            node.body_span.set_start(l, c, o);
+        }
+        true
+    }
+
+    /// Sets the end coordinates of the current node before leaving it.
+    fn set_end_spans(&self) {
+        let mut node = self.context_state.context_node.borrow_mut();
+        if self.rules.contains(&node.context_type) {
+            let line = self.line_counter;
+            let text = self.line_text(line, "");
+            let (l, c, o) = self.point(line, text, text.trim_end().len());
+            node.span.set_end(l, c, o);
+        } else {
+            close_block_node(&mut node, &self.file_lines, &self.line_offsets, self.line_counter);
+        }
+    }
+
+    /// Joins the lines of a definition header, from the keyword to the colon.
+    fn header_text(
+        &self,
+        line: usize,
+        current_line: &str,
+        column: usize,
+        end_line: usize,
+        end_index: usize,
+    ) -> String {
+        if end_line == line {
+            return current_line[column..end_index].to_string();
+        }
+        let mut header = current_line[column..].to_string();
+        for index in line + 1..end_line {
+            header.push('\n');
+            header.push_str(self.line_text(index, ""));
+        }
+        header.push('\n');
+        header.push_str(&self.line_text(end_line, "")[..end_index]);
+        header
+    }
+
+    /// Returns the line at the given index, falling back to the line under
+    /// processing when the module lines are not available.
+    fn line_text<'a>(&'a self, line: usize, current_line: &'a str) -> &'a str {
+        self.file_lines.get(line).map(String::as_str).unwrap_or(current_line)
+    }
+
+    /// Finds the colon closing a definition header, which might span several
+    /// lines when parameters or bases are wrapped in brackets.
+    fn find_header_end(&self, line: usize, current_line: &str, from: usize) -> Option<(usize, usize)> {
+        let mut depth = 0usize;
+        let mut line_index = line;
+        let mut text = current_line;
+        let mut start = from;
+        loop {
+            let mut quote: Option<char> = None;
+            for (i, ch) in text.char_indices().skip_while(|(i, _)| *i < start) {
+                match (quote, ch) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => (),
+                    (None, '\'' | '"') => quote = Some(ch),
+                    (None, '#') => break,
+                    (None, '(' | '[' | '{') => depth += 1,
+                    (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+                    (None, ':') if depth == 0 => return Some((line_index, i + 1)),
+                    _ => (),
+                }
+            }
+            line_index += 1;
+            text = self.file_lines.get(line_index)?;
+            start = 0;
+        }
     }
 }
+
+/// Checks if an `except` clause catches the errors raised by failing imports.
+fn catches_import_error(header: &str) -> bool {
+    header.contains("ImportError") || header.contains("ModuleNotFoundError")
+}
+
+/// Width of the leading whitespace of a line
+pub(super) fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn point(offsets: &[usize], line: usize, text: &str, index: usize) -> (usize, usize, Option<usize>) {
+    let column = text[..index].chars().count();
+    (line, column, offsets.get(line).map(|o| o + index))
+}
+
+/// Records the variant signatures on the implementation, or on the first
+/// variant when there is none. The docstring comes from the implementation,
+/// falling back to the first documented variant.
+fn merge_overloads(
+    implementation: Option<Rc<RefCell<ContextNode>>>,
+    variants: &[Rc<RefCell<ContextNode>>],
+) -> Rc<RefCell<ContextNode>> {
+    let signatures = variants
+        .iter()
+        .filter_map(|variant| variant.borrow().signature.clone())
+        .collect();
+    let docstring = variants.iter().find_map(|variant| {
+        variant
+            .borrow()
+            .children
+            .iter()
+            .find(|c| c.borrow().context_type == definitions::ContextType::DOCSTRING)
+            .map(Rc::clone)
+    });
+    let group = match implementation {
+        Some(implementation) => implementation,
+        None => {
+            let mut first = variants[0].borrow_mut();
+            first.signature = None;
+            // The group stands for every variant, not for the first one
+            first.decorators.retain(|d| d.rsplit('.').next() != Some("overload"));
+            drop(first);
+            Rc::clone(&variants[0])
+        }
+    };
+
+    let mut node = group.borrow_mut();
+    node.overloads = signatures;
+    if node.docstring().is_none() {
+        if let Some(docstring) = docstring {
+            docstring.borrow_mut().set_parent(Rc::clone(&group));
+            node.children.insert(0, docstring);
+        }
+    }
+    drop(node);
+    group
+}
+
+/// Sets the end of a block node to the end of its last line holding code,
+/// searching backwards from the line that closed the block.
+fn close_block_node(node: &mut ContextNode, lines: &[String], offsets: &[usize], before: usize) {
+    let start = node.span.start_line.unwrap_or(0);
+    let last = (start..before.min(lines.len()))
+        .rev()
+        .find(|i| {
+            let trimmed = lines[*i].trim_start();
+            !trimmed.is_empty() && !trimmed.starts_with('#')
+        })
+        .unwrap_or(start);
+    let text = lines.get(last).map(String::as_str).unwrap_or("");
+    let (l, c, o) = point(offsets, last, text, text.trim_end().len());
+    node.span.set_end(l, c, o);
+    if node.signature_span.start_line.is_some() {
+        if node.body_span.start_line.is_some() {
+            node.body_span.set_end(l, c, o);
+        } else {
+            node.body_span = Span::default();
+        }
+    }
+}
+
+/// Marks the lines whose indentation is not significant because they
+/// continue a logical line started above.
+fn find_continuations(lines: &[String]) -> Vec<bool> {
+    let mut continuations = Vec::with_capacity(lines.len());
+    let mut depth = 0usize;
+    let mut triple: Option<char> = None;
+    let mut backslash = false;
+
+    for line in lines {
+        continuations.push(depth > 0 || triple.is_some() || backslash);
+        backslash = false;
+
+        let chars: Vec<char> = line.chars().collect();
+        let mut quote: Option<char> = None;
+        let mut i = 0;
+        while i < chars.len() {
+            let ch = chars[i];
+            let opens_triple = i + 2 < chars.len() && chars[i + 1] == ch && chars[i + 2] == ch;
+            if let Some(q) = triple {
+                if ch == '\\' {
+                    i += 1;
+                } else if ch == q && opens_triple {
+                    triple = None;
+                    i += 2;
+                }
+            } else if let Some(q) = quote {
+                if ch == '\\' {
+                    i += 1;
+                } else if ch == q {
+                    quote = None;
+                }
+            } else {
+                match ch {
+                    '#' => break,
+                    '\'' | '"' if opens_triple => {
+                        triple = Some(ch);
+                        i += 2;
+                    }
+                    '\'' | '"' => quote = Some(ch),
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth = depth.saturating_sub(1),
+                    '\\' if i + 1 == chars.len() => backslash = true,
+                    _ => (),
+                }
+            }
+            i += 1;
+        }
+    }
+
+    continuations
+}
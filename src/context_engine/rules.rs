@@ -1,29 +1,204 @@
-use super::definitions::ContextType;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+use super::definitions::{ContextNode, ContextType};
+use super::syntax::{Guard, GuardKind};
 
-/// Contains a global vector of context types that should be omitted when
-/// walking through the definitions of a py module.
+/// Docstring convention of a module, deciding which section headers are
+/// recognized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocstringStyle {
+    /// Any of the conventions below
+    Auto,
+    Google,
+    Numpy,
+    Sphinx,
+}
+
+/// Rules applied by the processor when walking through the definitions of a
+/// py module.
 ///
-/// For example: Docstrings do not represent a specific defined context like
+/// Logic contexts are omitted when walking through definitions. For
+/// example: Docstrings do not represent a specific defined context like
 /// a class or method. Exiting from a docstring should not lead to the same
-/// rules as exiting from methods or classes
+/// rules as exiting from methods or classes.
+///
+/// The remaining rules decide which of the parsed nodes are kept in the
+/// resulting tree. Every setter consumes and returns the rules so they can
+/// be chained:
+///
+/// ```
+/// use chapter::context_engine::definitions::ContextType;
+/// use chapter::context_engine::rules::LogicContext;
+///
+/// let rules = LogicContext::new()
+///     .track(vec![ContextType::CLASS, ContextType::METHOD])
+///     .nested_functions(false)
+///     .max_depth(2)
+///     .keep_private(false);
+/// ```
 pub struct LogicContext {
-    contexts: Vec<ContextType>
+    contexts: Vec<ContextType>,
+    tracked: Vec<ContextType>,
+    nested_functions: bool,
+    max_depth: Option<usize>,
+    keep_private: bool,
+    assumptions: Vec<(String, bool)>,
+    pub(crate) docstring_style: DocstringStyle,
 }
 
 impl LogicContext {
     pub fn new() -> Self {
+        Self {
+            contexts: vec![ContextType::DOCSTRING],
+            tracked: vec![
+                ContextType::CLASS,
+                ContextType::METHOD,
+                ContextType::ALL,
+                ContextType::IMPORT,
+                ContextType::VARIABLE,
+                ContextType::ALIAS,
+                ContextType::ATTRIBUTE,
+                ContextType::PROPERTY,
+                ContextType::DOCSTRING,
+            ],
+            nested_functions: true,
+            max_depth: None,
+            keep_private: true,
+            assumptions: Vec::new(),
+            docstring_style: DocstringStyle::Auto,
+        }
+    }
+
+    /// Sets the contexts that only hold values and are omitted when walking
+    /// through definitions.
+    pub fn logic_contexts(mut self, contexts: Vec<ContextType>) -> Self {
+        self.contexts = contexts;
+        self
+    }
+
+    /// Sets the contexts recorded in the tree. A context that is not tracked
+    /// is dropped together with everything defined inside it. User registered
+    /// contexts are always tracked.
+    pub fn track(mut self, contexts: Vec<ContextType>) -> Self {
+        self.tracked = contexts;
+        self
+    }
+
+    /// Sets whether functions defined inside other functions are recorded.
+    pub fn nested_functions(mut self, record: bool) -> Self {
+        self.nested_functions = record;
+        self
+    }
+
+    /// Sets the deepest level recorded in the tree, module members being on
+    /// level 1.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Sets whether private members (names starting with an underscore that
+    /// are not dunder names) are kept.
+    pub fn keep_private(mut self, keep: bool) -> Self {
+        self.keep_private = keep;
+        self
+    }
+
+    /// Sets the docstring convention of the module.
+    pub fn docstring_style(mut self, style: DocstringStyle) -> Self {
+        self.docstring_style = style;
+        self
+    }
+
+    /// Assumes the value of a condition guarding definitions, such as
+    /// `sys.version_info >= (3, 8)` or `TYPE_CHECKING`. Definitions in
+    /// `if`/`elif`/`else` branches that are not taken under the assumptions
+    /// are dropped, choosing which branch gets documented.
+    pub fn assume(mut self, condition: &str, value: bool) -> Self {
+        self.assumptions.push((normalize(condition), value));
+        self
+    }
+
+    /// Evaluates a condition against the assumptions, `None` when unknown.
+    fn evaluate(&self, condition: &str) -> Option<bool> {
+        let condition = normalize(condition);
+        if let Some((_, value)) = self.assumptions.iter().find(|(c, _)| *c == condition) {
+            return Some(*value);
+        }
+        condition.strip_prefix("not ").and_then(|c| self.evaluate(c)).map(|v| !v)
+    }
 
-        // NOTE: Add here all contexts that have logic or values in it
-        // to be omitted when walking through definitions
-        let contexts = vec![
-            ContextType::DOCSTRING
-        ];
+    /// Checks if the branch opened by a guard runs under the assumptions,
+    /// `None` when it cannot be decided.
+    pub fn branch_taken(&self, guard: &Guard) -> Option<bool> {
+        let previous = guard
+            .previous_conditions
+            .iter()
+            .map(|c| self.evaluate(c))
+            .collect::<Vec<Option<bool>>>();
+        if previous.contains(&Some(true)) {
+            return Some(false);
+        }
+        let previous_false = previous.iter().all(|p| *p == Some(false));
 
-        Self { contexts}
+        match guard.kind {
+            GuardKind::If => self.evaluate(guard.condition.as_deref()?),
+            GuardKind::Elif => match self.evaluate(guard.condition.as_deref()?)? {
+                false => Some(false),
+                true => previous_false.then_some(true),
+            },
+            GuardKind::Else if !previous.is_empty() => previous_false.then_some(true),
+            _ => None,
+        }
     }
 
     pub fn contains(&self, context: &ContextType) -> bool{
         self.contexts.contains(context)
     }
-}
\ No newline at end of file
+
+    /// Removes the children of the node that are not allowed by the rules.
+    pub fn prune(&self, node: &Rc<RefCell<ContextNode>>) {
+        self.prune_level(node, 1, false);
+    }
+
+    fn prune_level(&self, node: &Rc<RefCell<ContextNode>>, depth: usize, in_function: bool) {
+        let in_function = in_function
+            || matches!(node.borrow().context_type, ContextType::METHOD | ContextType::PROPERTY);
+        node.borrow_mut()
+            .children
+            .retain(|child| self.keeps(&child.borrow(), depth, in_function));
+        for child in node.borrow().children.iter() {
+            self.prune_level(child, depth + 1, in_function);
+        }
+    }
+
+    fn keeps(&self, node: &ContextNode, depth: usize, in_function: bool) -> bool {
+        let custom = matches!(node.context_type, ContextType::CUSTOM(_));
+        if !custom && !self.tracked.contains(&node.context_type) {
+            return false;
+        }
+        if !self.nested_functions && in_function && node.context_type == ContextType::METHOD {
+            return false;
+        }
+        // Docstrings belong to the deepest kept definitions
+        let docstring = node.context_type == ContextType::DOCSTRING;
+        if !docstring && self.max_depth.is_some_and(|max| depth > max) {
+            return false;
+        }
+        if node.guards.iter().any(|g| self.branch_taken(g) == Some(false)) {
+            return false;
+        }
+        self.keep_private || node.is_public
+    }
+}
+
+fn normalize(condition: &str) -> String {
+    condition.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+impl Default for LogicContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
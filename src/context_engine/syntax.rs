@@ -0,0 +1,1044 @@
+//! Helpers reading the pieces of python definition headers.
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Replacement named by a deprecation message, e.g. "use `g` instead"
+static REPLACEMENT_MATCH: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\buse\s+(\S+)").unwrap());
+
+/// Removal version named by a deprecation message, e.g. "removed in 3.0"
+static REMOVAL_MATCH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\bremov(?:ed|al)\s+in\s+(\S+)").unwrap());
+
+/// Standard library enum classes.
+const ENUM_BASES: [&str; 6] = ["Enum", "IntEnum", "StrEnum", "Flag", "IntFlag", "ReprEnum"];
+
+/// Details found in the header of a class definition.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ClassHeader {
+    /// PEP 695 type parameters, e.g. `T` and `U: int` in `class A[T, U: int]:`
+    pub type_params: Vec<String>,
+
+    /// Base class expressions in declaration order
+    pub bases: Vec<String>,
+
+    /// Expression given as `metaclass=`
+    pub metaclass: Option<String>,
+
+    /// Any other class keyword, e.g. `total=False`
+    pub keywords: Vec<(String, String)>,
+}
+
+impl ClassHeader {
+    /// Parses a header such as `class Foo(Base, metaclass=Meta):`. The text
+    /// may span several lines and hold comments.
+    pub fn parse(header: &str) -> Self {
+        let header = strip_comments(header);
+        let rest = header.trim_start();
+        let rest = rest.strip_prefix("class").unwrap_or(rest).trim_start();
+        let rest = rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_');
+
+        let mut class_header = Self::default();
+        let rest = match enclosed(rest, '[', ']') {
+            Some((params, rest)) => {
+                class_header.type_params = split_top_level(params);
+                rest
+            }
+            None => rest,
+        };
+
+        if let Some((arguments, _)) = enclosed(rest, '(', ')') {
+            for argument in split_top_level(arguments) {
+                match keyword_argument(&argument) {
+                    Some((key, value)) if key == "metaclass" => class_header.metaclass = Some(value),
+                    Some((key, value)) => class_header.keywords.push((key, value)),
+                    None => class_header.bases.push(argument),
+                }
+            }
+        }
+
+        class_header
+    }
+
+    /// Returns the enum base class (`Enum`, `IntEnum`, `StrEnum`, `Flag`,
+    /// ...) the class directly derives from, if any.
+    pub fn enum_base(&self) -> Option<&str> {
+        self.bases
+            .iter()
+            .map(|base| base.rsplit('.').next().unwrap_or(base).trim())
+            .find(|base| ENUM_BASES.contains(base))
+    }
+
+    /// Returns the names of the generic type parameters, either declared with
+    /// PEP 695 syntax or through `Generic[...]`/`Protocol[...]` bases.
+    pub fn generic_parameters(&self) -> Vec<String> {
+        if !self.type_params.is_empty() {
+            return self
+                .type_params
+                .iter()
+                .map(|p| {
+                    let name = p.split([':', '=']).next().unwrap_or(p);
+                    name.trim().trim_start_matches('*').to_string()
+                })
+                .collect();
+        }
+
+        self.bases
+            .iter()
+            .filter_map(|base| {
+                let (name, params) = base.split_once('[')?;
+                let name = name.trim().rsplit('.').next()?;
+                if name != "Generic" && name != "Protocol" {
+                    return None;
+                }
+                Some(split_top_level(params.strip_suffix(']')?))
+            })
+            .next()
+            .unwrap_or_default()
+    }
+}
+
+/// Splits on commas that are not nested inside brackets or strings. Items are
+/// trimmed and empty items (trailing commas) are skipped.
+pub fn split_top_level(text: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+
+    for ch in text.chars() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(ch),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                items.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => (),
+        }
+        current.push(ch);
+    }
+    items.push(current.trim().to_string());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+/// Returns the text between an opening bracket at the start of `text` and
+/// its matching closing bracket, followed by the rest of the text.
+pub fn enclosed(text: &str, open: char, close: char) -> Option<(&str, &str)> {
+    let text = text.trim_start();
+    if !text.starts_with(open) {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for (i, ch) in text.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(ch),
+            (None, c) if c == open => depth += 1,
+            (None, c) if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&text[1..i], &text[i + 1..]));
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Splits `key=value` arguments, leaving comparisons such as `a == b` alone.
+pub fn keyword_argument(argument: &str) -> Option<(String, String)> {
+    let (key, value) = argument.split_once('=')?;
+    let key = key.trim();
+    if value.starts_with('=') || key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((key.to_string(), value.trim().to_string()))
+}
+
+/// Removes `#` comments from every line, keeping `#` inside strings.
+pub fn strip_comments(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            let mut quote: Option<char> = None;
+            for (i, ch) in line.char_indices() {
+                match (quote, ch) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => (),
+                    (None, '\'' | '"') => quote = Some(ch),
+                    (None, '#') => return &line[..i],
+                    _ => (),
+                }
+            }
+            line
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Name bound by an import statement.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ImportedName {
+    /// Imported name, dotted for `import a.b`
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+impl ImportedName {
+    /// Returns the name bound in the importing module.
+    pub fn bound_name(&self) -> &str {
+        match &self.alias {
+            Some(alias) => alias,
+            None => self.name.split('.').next().unwrap_or(&self.name),
+        }
+    }
+}
+
+/// An `import ...` or `from ... import ...` statement.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct ImportStatement {
+    /// Module after `from`, without the leading dots. Empty for plain
+    /// `import` statements and for `from . import x`.
+    pub module: String,
+    pub names: Vec<ImportedName>,
+
+    /// Number of leading dots of a relative import
+    pub level: usize,
+
+    /// Whether the statement has the `from ... import ...` form
+    pub is_from: bool,
+
+    /// Whether the statement is guarded by `if TYPE_CHECKING:`
+    pub type_checking: bool,
+
+    /// Whether a failure of the import is handled by `except ImportError`
+    pub import_error_guard: bool,
+}
+
+impl ImportStatement {
+    /// Parses an import statement, which may span several lines.
+    pub fn parse(statement: &str) -> Option<Self> {
+        let statement = strip_comments(statement).replace(['(', ')', '\\'], " ");
+        let statement = statement.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let statement = statement.split(';').next().unwrap_or_default();
+
+        let mut import = Self::default();
+        let names = if let Some(rest) = statement.strip_prefix("from ") {
+            let (module, names) = rest.split_once(" import ")?;
+            import.is_from = true;
+            import.level = module.chars().take_while(|c| *c == '.').count();
+            import.module = module[import.level..].to_string();
+            names
+        } else {
+            statement.strip_prefix("import ")?
+        };
+
+        import.names = names
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split_whitespace();
+                let name = parts.next()?.to_string();
+                let alias = match (parts.next(), parts.next()) {
+                    (Some("as"), Some(alias)) => Some(alias.to_string()),
+                    _ => None,
+                };
+                Some(ImportedName { name, alias })
+            })
+            .collect();
+
+        Some(import)
+    }
+
+    /// Returns the module as written in the source, e.g. `..base`, or the
+    /// imported modules of a plain `import` statement.
+    pub fn source_module(&self) -> String {
+        if self.is_from {
+            return format!("{}{}", ".".repeat(self.level), self.module);
+        }
+        self.names
+            .iter()
+            .map(|n| n.name.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+}
+
+/// Typing constructs that make an assignment an implicit type alias.
+const TYPE_FORMS: [&str; 12] = [
+    "Literal[", "Union[", "Optional[", "Callable[", "Annotated[", "Tuple[", "Dict[", "List[",
+    "Set[", "Type[", "TypedDict(", "NamedTuple(",
+];
+
+/// Calls declaring type variables.
+const TYPE_VARIABLES: [&str; 4] = ["TypeVar(", "ParamSpec(", "TypeVarTuple(", "NewType("];
+
+const KEYWORDS: [&str; 16] = [
+    "else", "try", "finally", "except", "if", "elif", "while", "for", "with", "return",
+    "pass", "break", "continue", "raise", "del", "lambda",
+];
+
+/// Annotated and/or assigned name, e.g. `TIMEOUT: int = 30` or a PEP 695
+/// `type Alias[T] = list[T]` statement.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Variable {
+    pub annotation: Option<String>,
+
+    /// Source of the assigned expression
+    pub value: Option<String>,
+
+    /// Whether the variable is declared with the `type` statement
+    pub type_statement: bool,
+
+    /// PEP 695 type parameters of a `type` statement
+    pub type_params: Vec<String>,
+}
+
+impl Variable {
+    /// Parses an assignment or a `type` statement, returning the assigned
+    /// name and the variable. Targets other than a single name (tuples,
+    /// attributes, subscripts) and augmented assignments are ignored.
+    pub fn parse(statement: &str) -> Option<(String, Self)> {
+        let statement = strip_comments(statement);
+        let statement = statement.trim();
+        let mut variable = Self::default();
+
+        if let Some(rest) = statement.strip_prefix("type ") {
+            let rest = rest.trim_start();
+            let name_end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let (name, rest) = rest.split_at(name_end);
+            let rest = match enclosed(rest, '[', ']') {
+                Some((params, rest)) => {
+                    variable.type_params = split_top_level(params);
+                    rest
+                }
+                None => rest,
+            };
+            let value = rest.trim_start().strip_prefix('=')?;
+            variable.type_statement = true;
+            variable.value = Some(value.trim().to_string());
+            return Some((name.to_string(), variable));
+        }
+
+        let (target, value) = match find_top_level(statement, '=') {
+            Some(index) => (&statement[..index], Some(&statement[index + 1..])),
+            None => (statement, None),
+        };
+        let (name, annotation) = match target.split_once(':') {
+            Some((name, annotation)) => (name.trim(), Some(annotation.trim())),
+            None => (target.trim(), None),
+        };
+        if name.is_empty()
+            || KEYWORDS.contains(&name)
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || !name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            || (annotation.is_none() && value.is_none())
+            || annotation.is_some_and(str::is_empty)
+        {
+            return None;
+        }
+
+        variable.annotation = annotation.map(String::from);
+        variable.value = value.map(|v| v.trim().to_string());
+        Some((name.to_string(), variable))
+    }
+
+    /// Checks if the variable is a type alias, either explicit (`type`
+    /// statement, `TypeAlias` annotation) or assigned a typing construct.
+    pub fn is_alias(&self) -> bool {
+        if self.type_statement {
+            return true;
+        }
+        if let Some(annotation) = &self.annotation {
+            return annotation.rsplit('.').next() == Some("TypeAlias");
+        }
+        let value = match &self.value {
+            Some(value) => value.strip_prefix("typing.").unwrap_or(value),
+            None => return false,
+        };
+        TYPE_FORMS.iter().any(|form| value.starts_with(form))
+    }
+
+    /// Checks if the variable declares a type variable or a new type.
+    pub fn is_type_variable(&self) -> bool {
+        let value = match &self.value {
+            Some(value) => value.strip_prefix("typing.").unwrap_or(value),
+            None => return false,
+        };
+        TYPE_VARIABLES.iter().any(|call| value.starts_with(call))
+    }
+}
+
+/// Returns the byte index of the first occurrence of a character outside of
+/// brackets and strings. An `=` only matches a plain assignment, not `==`,
+/// `<=` or augmented assignments.
+pub fn find_top_level(text: &str, target: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let bytes = text.as_bytes();
+
+    for (i, ch) in text.char_indices() {
+        match (quote, ch) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(ch),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, c) if c == target && depth == 0 => {
+                if target != '=' {
+                    return Some(i);
+                }
+                let previous = if i > 0 { bytes[i - 1] } else { b' ' };
+                let next = bytes.get(i + 1).copied().unwrap_or(b' ');
+                if next != b'=' && !b"=<>!+-*/%&|^@:".contains(&previous) {
+                    return Some(i);
+                }
+                return None;
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Class decorators turning annotated class attributes into fields.
+const DATA_CLASS_DECORATORS: [&str; 2] = ["dataclass", "dataclass_transform"];
+
+/// attrs decorators, whose names are too common to be matched without their
+/// module.
+const ATTRS_DECORATORS: [&str; 9] = [
+    "attr.s", "attr.attrs", "attr.define", "attr.mutable", "attr.frozen",
+    "attrs.define", "attrs.mutable", "attrs.frozen", "attrs.attrs",
+];
+
+/// Bases turning annotated class attributes into fields.
+const DATA_CLASS_BASES: [&str; 5] = ["BaseModel", "BaseSettings", "TypedDict", "NamedTuple", "Struct"];
+
+/// Calls configuring a field of a data class.
+const FIELD_CALLS: [&str; 5] = ["field", "Field", "ib", "attrib", "PrivateAttr"];
+
+/// Where an attribute of a class is declared.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum AttributeKind {
+    /// Assigned or annotated in the class body
+    Class,
+
+    /// Assigned on `self` inside `__init__`
+    Instance,
+
+    /// Listed in `__slots__`
+    Slot,
+
+    /// Field of a dataclass, attrs class or pydantic model
+    Field,
+}
+
+/// Details of a class attribute, complementing its `Variable`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Attribute {
+    pub kind: AttributeKind,
+
+    /// Default value of class attributes and fields
+    pub default: Option<String>,
+
+    /// Factory given as `default_factory=` or `factory=`
+    pub default_factory: Option<String>,
+
+    /// Remaining keyword arguments of `field(...)`-like calls
+    pub field_arguments: Vec<(String, String)>,
+}
+
+impl Attribute {
+    pub fn new(kind: AttributeKind) -> Self {
+        Self {
+            kind,
+            default: None,
+            default_factory: None,
+            field_arguments: Vec::new(),
+        }
+    }
+
+    /// Creates a field from the value assigned to it, reading the arguments
+    /// of `field(...)`, `attr.ib(...)` or `Field(...)` calls.
+    pub fn field(value: Option<&str>) -> Self {
+        let mut attribute = Self::new(AttributeKind::Field);
+        let value = match value {
+            Some(value) => value.trim(),
+            None => return attribute,
+        };
+        let call = value.split('(').next().unwrap_or_default().trim();
+        let arguments = match enclosed(&value[call.len()..], '(', ')') {
+            Some((arguments, _)) if FIELD_CALLS.contains(&call.rsplit('.').next().unwrap_or(call)) => {
+                split_top_level(arguments)
+            }
+            _ => {
+                attribute.default = Some(value.to_string());
+                return attribute;
+            }
+        };
+
+        for argument in arguments {
+            match keyword_argument(&argument) {
+                Some((key, value)) if key == "default" => attribute.default = Some(value),
+                Some((key, value)) if key == "default_factory" || key == "factory" => {
+                    attribute.default_factory = Some(value)
+                }
+                Some(keyword) => attribute.field_arguments.push(keyword),
+                None if attribute.default.is_none() && argument != "..." => {
+                    attribute.default = Some(argument)
+                }
+                None => (),
+            }
+        }
+        attribute
+    }
+}
+
+/// Checks if the fields of a class are declared through annotated class
+/// attributes, based on its decorators and bases.
+pub fn is_data_class(decorators: &[String], header: Option<&ClassHeader>) -> bool {
+    let decorated = decorators.iter().any(|decorator| {
+        let name = decorator.split('(').next().unwrap_or(decorator).trim();
+        DATA_CLASS_DECORATORS.contains(&name.rsplit('.').next().unwrap_or(name))
+            || ATTRS_DECORATORS.contains(&name)
+    });
+    let derived = header.is_some_and(|header| {
+        header.bases.iter().any(|base| {
+            let name = base.split('[').next().unwrap_or(base).trim();
+            DATA_CLASS_BASES.contains(&name.rsplit('.').next().unwrap_or(name))
+        })
+    });
+    decorated || derived
+}
+
+/// Reads the attribute names listed by a `__slots__` value, together with
+/// their docstring when the slots are given as a dict.
+pub fn slot_names(value: &str) -> Vec<(String, Option<String>)> {
+    let value = value.trim();
+    let items = match value.chars().next() {
+        Some('(') => enclosed(value, '(', ')').map(|(items, _)| items),
+        Some('[') => enclosed(value, '[', ']').map(|(items, _)| items),
+        Some('{') => enclosed(value, '{', '}').map(|(items, _)| items),
+        _ => Some(value),
+    };
+
+    split_top_level(items.unwrap_or_default())
+        .into_iter()
+        .filter_map(|item| {
+            let (name, doc) = match find_top_level(&item, ':') {
+                Some(index) => (item[..index].to_string(), Some(item[index + 1..].trim().to_string())),
+                None => (item, None),
+            };
+            let name = name.trim().trim_matches(['"', '\'']).to_string();
+            (!name.is_empty()).then_some((name, doc))
+        })
+        .collect()
+}
+
+/// Member of an enum class.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct EnumMember {
+    pub name: String,
+
+    /// Source of the assigned value, e.g. `"red"` or `auto()`
+    pub value: String,
+
+    /// Value of the member when it is a literal or an `auto()` call that can
+    /// be computed statically
+    pub resolved_value: Option<String>,
+
+    /// Attribute docstring following the member
+    pub docstring: Option<String>,
+
+    /// Comment written after the member or on the lines right above it
+    pub comment: Option<String>,
+
+    /// Zero based line of the member
+    pub line: usize,
+}
+
+impl EnumMember {
+    /// Checks if the value is generated with `auto()`.
+    pub fn is_auto(&self) -> bool {
+        is_auto(&self.value)
+    }
+}
+
+fn is_auto(value: &str) -> bool {
+    value.trim().rsplit('.').next() == Some("auto()")
+}
+
+/// Computes the values of the members generated with `auto()` and parses
+/// literal values, following the rules of the given enum base.
+pub fn resolve_enum_values(base: &str, members: &mut [EnumMember]) {
+    let mut last: Option<i128> = None;
+    for member in members.iter_mut() {
+        let value = member.value.trim();
+        member.resolved_value = if is_auto(value) {
+            match base {
+                "StrEnum" => Some(format!("'{}'", member.name.to_lowercase())),
+                "Flag" | "IntFlag" => {
+                    let next = match last {
+                        Some(l) if l > 0 => 1i128 << (128 - l.leading_zeros()),
+                        _ => 1,
+                    };
+                    last = Some(next);
+                    Some(next.to_string())
+                }
+                _ => {
+                    let next = last.map_or(1, |l| l + 1);
+                    last = Some(next);
+                    Some(next.to_string())
+                }
+            }
+        } else if let Ok(number) = value.replace('_', "").parse::<i128>() {
+            last = Some(number);
+            Some(number.to_string())
+        } else if value.len() >= 2
+            && (value.starts_with('"') || value.starts_with('\''))
+            && value.ends_with(&value[..1])
+        {
+            Some(value.to_string())
+        } else {
+            None
+        };
+    }
+}
+
+/// Control flow statement opening a block.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum GuardKind {
+    If,
+    Elif,
+    Else,
+    Try,
+    Except,
+    Finally,
+    With,
+    For,
+    While,
+    Match,
+    Case,
+}
+
+/// Control flow block a definition is nested in, between the definition and
+/// its enclosing class, function or module.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Guard {
+    pub kind: GuardKind,
+
+    /// Expression of the header: the test of `if`/`elif`/`while`, the caught
+    /// exceptions, the context managers, the loop target or the pattern
+    pub condition: Option<String>,
+
+    /// Tests of the `if`/`elif` branches preceding an `elif` or `else`
+    /// branch, in source order
+    pub previous_conditions: Vec<String>,
+
+    /// Zero based line of the header
+    pub line: usize,
+}
+
+impl Guard {
+    /// Parses a block header such as `if sys.version_info >= (3, 8):`.
+    pub fn parse(header: &str, line: usize) -> Option<Self> {
+        let header = strip_comments(header);
+        let header = header.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let header = header.strip_suffix(':')?.trim();
+        let (keyword, rest) = header.split_once(' ').unwrap_or((header, ""));
+        let keyword = match keyword {
+            "async" => rest.split(' ').next()?,
+            keyword => keyword,
+        };
+        let rest = header
+            .strip_prefix("async ")
+            .unwrap_or(header)
+            .strip_prefix(keyword)?
+            .trim();
+
+        let kind = match keyword {
+            "if" => GuardKind::If,
+            "elif" => GuardKind::Elif,
+            "else" => GuardKind::Else,
+            "try" => GuardKind::Try,
+            "except" | "except*" => GuardKind::Except,
+            "finally" => GuardKind::Finally,
+            "with" => GuardKind::With,
+            "for" => GuardKind::For,
+            "while" => GuardKind::While,
+            "match" => GuardKind::Match,
+            "case" => GuardKind::Case,
+            _ => return None,
+        };
+        let condition = (!rest.is_empty()).then(|| rest.to_string());
+
+        Some(Self {
+            kind,
+            condition,
+            previous_conditions: Vec::new(),
+            line,
+        })
+    }
+}
+
+/// Accessors of a property, merged from the methods decorated with
+/// `@property`, `@x.setter` and `@x.deleter`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Property {
+    pub readable: bool,
+    pub writable: bool,
+    pub deletable: bool,
+
+    /// Return annotation of the getter
+    pub annotation: Option<String>,
+
+    /// Cached with `functools.cached_property`
+    pub cached: bool,
+}
+
+impl Property {
+    /// Reads the role of a decorator applied to a method named `name`:
+    /// creating the property or adding an accessor to it.
+    pub fn accessor(decorator: &str, name: &str) -> Option<PropertyAccessor> {
+        match decorator {
+            "property" | "builtins.property" => return Some(PropertyAccessor::Getter),
+            "cached_property" | "functools.cached_property" => return Some(PropertyAccessor::Cached),
+            _ => (),
+        }
+        match decorator.strip_prefix(name)?.strip_prefix('.')? {
+            "getter" => Some(PropertyAccessor::Getter),
+            "setter" => Some(PropertyAccessor::Setter),
+            "deleter" => Some(PropertyAccessor::Deleter),
+            _ => None,
+        }
+    }
+}
+
+/// Role of a method in a property.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum PropertyAccessor {
+    Getter,
+    Cached,
+    Setter,
+    Deleter,
+}
+
+/// How an argument is bound to a parameter.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum ParameterKind {
+    /// Declared before `/`
+    PositionalOnly,
+    PositionalOrKeyword,
+
+    /// `*args`
+    VarPositional,
+
+    /// Declared after `*` or `*args`
+    KeywordOnly,
+
+    /// `**kwargs`
+    VarKeyword,
+}
+
+/// Parameter of a function signature.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Parameter {
+    /// Name without the leading `*` or `**`
+    pub name: String,
+    pub kind: ParameterKind,
+    pub annotation: Option<String>,
+    pub default: Option<String>,
+}
+
+impl Parameter {
+    /// Renders the parameter as written in a signature.
+    pub fn render(&self) -> String {
+        let prefix = match self.kind {
+            ParameterKind::VarPositional => "*",
+            ParameterKind::VarKeyword => "**",
+            _ => "",
+        };
+        match (&self.annotation, &self.default) {
+            (Some(a), Some(d)) => format!("{}{}: {} = {}", prefix, self.name, a, d),
+            (Some(a), None) => format!("{}{}: {}", prefix, self.name, a),
+            (None, Some(d)) => format!("{}{}={}", prefix, self.name, d),
+            (None, None) => format!("{}{}", prefix, self.name),
+        }
+    }
+}
+
+/// Parameters and return annotation of a function definition.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct FunctionSignature {
+    pub is_async: bool,
+
+    /// PEP 695 type parameters
+    pub type_params: Vec<String>,
+    pub parameters: Vec<Parameter>,
+    pub return_annotation: Option<String>,
+}
+
+impl FunctionSignature {
+    /// Parses a header such as `async def f(a, /, b: int = 1, *, c) -> str:`.
+    /// The text may span several lines and hold comments.
+    pub fn parse(header: &str) -> Option<Self> {
+        let header = strip_comments(header);
+        let mut rest = header.trim_start();
+        let mut signature = Self::default();
+        if let Some(r) = rest.strip_prefix("async") {
+            signature.is_async = true;
+            rest = r.trim_start();
+        }
+        rest = rest.strip_prefix("def")?.trim_start();
+        rest = rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_');
+        if let Some((params, r)) = enclosed(rest, '[', ']') {
+            signature.type_params = split_top_level(params);
+            rest = r;
+        }
+        let (params, rest) = enclosed(rest, '(', ')')?;
+
+        let mut kind = ParameterKind::PositionalOrKeyword;
+        for item in split_top_level(params) {
+            match item.as_str() {
+                "/" => {
+                    for p in signature.parameters.iter_mut() {
+                        p.kind = ParameterKind::PositionalOnly;
+                    }
+                    continue;
+                }
+                "*" => {
+                    kind = ParameterKind::KeywordOnly;
+                    continue;
+                }
+                _ => (),
+            }
+
+            let (target, default) = match find_top_level(&item, '=') {
+                Some(index) => (&item[..index], Some(item[index + 1..].trim().to_string())),
+                None => (item.as_str(), None),
+            };
+            let (name, annotation) = match find_top_level(target, ':') {
+                Some(index) => (target[..index].trim(), Some(target[index + 1..].trim().to_string())),
+                None => (target.trim(), None),
+            };
+            let (name, parameter_kind) = if let Some(name) = name.strip_prefix("**") {
+                (name, ParameterKind::VarKeyword)
+            } else if let Some(name) = name.strip_prefix('*') {
+                kind = ParameterKind::KeywordOnly;
+                (name, ParameterKind::VarPositional)
+            } else {
+                (name, kind)
+            };
+            signature.parameters.push(Parameter {
+                name: name.to_string(),
+                kind: parameter_kind,
+                annotation,
+                default,
+            });
+        }
+
+        let rest = rest.trim().trim_end_matches(':').trim();
+        signature.return_annotation = rest
+            .strip_prefix("->")
+            .map(|annotation| annotation.trim().to_string());
+        Some(signature)
+    }
+
+    /// Renders the parameter list and return annotation, e.g.
+    /// `(a, /, b: int = 1, *, c) -> str`.
+    pub fn render(&self) -> String {
+        let mut items = Vec::new();
+        let mut keyword_marker = false;
+        for (index, parameter) in self.parameters.iter().enumerate() {
+            match parameter.kind {
+                ParameterKind::KeywordOnly if !keyword_marker => {
+                    items.push(String::from("*"));
+                    keyword_marker = true;
+                }
+                ParameterKind::VarPositional => keyword_marker = true,
+                _ => (),
+            }
+            items.push(parameter.render());
+            let next_kind = self.parameters.get(index + 1).map(|p| p.kind);
+            if parameter.kind == ParameterKind::PositionalOnly
+                && next_kind != Some(ParameterKind::PositionalOnly)
+            {
+                items.push(String::from("/"));
+            }
+        }
+
+        let type_params = match self.type_params.is_empty() {
+            true => String::new(),
+            false => format!("[{}]", self.type_params.join(", ")),
+        };
+        match &self.return_annotation {
+            Some(annotation) => format!("{}({}) -> {}", type_params, items.join(", "), annotation),
+            None => format!("{}({})", type_params, items.join(", ")),
+        }
+    }
+}
+
+/// Where a deprecation was declared.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum DeprecationSource {
+    /// `@deprecated` from PEP 702 or the `deprecated` package
+    Decorator,
+
+    /// `warnings.warn(..., DeprecationWarning)` at the top of the body
+    Warning,
+
+    /// `.. deprecated::` directive of the docstring
+    Directive,
+}
+
+/// Deprecation of a symbol, merged from every place declaring it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Deprecation {
+    pub source: DeprecationSource,
+
+    /// Version the symbol was deprecated in
+    pub version: Option<String>,
+    pub message: Option<String>,
+
+    /// Symbol to use instead, read from phrases like "use `x` instead"
+    pub replacement: Option<String>,
+
+    /// Version the symbol will be removed in, read from phrases like
+    /// "removed in 3.0"
+    pub removal: Option<String>,
+}
+
+impl Deprecation {
+    fn new(source: DeprecationSource, version: Option<String>, message: Option<String>) -> Self {
+        let replacement = message.as_deref().and_then(|m| phrase_target(m, &REPLACEMENT_MATCH));
+        let removal = message.as_deref().and_then(|m| phrase_target(m, &REMOVAL_MATCH));
+        Self {
+            source,
+            version,
+            message,
+            replacement,
+            removal,
+        }
+    }
+
+    /// Reads a decorator such as `deprecated("Use g instead")` or
+    /// `deprecated(version="1.2", reason="...")`.
+    pub fn from_decorator(decorator: &str) -> Option<Self> {
+        let name = decorator.split('(').next().unwrap_or(decorator).trim();
+        if name.rsplit('.').next() != Some("deprecated") {
+            return None;
+        }
+        let (mut version, mut message) = (None, None);
+        if let Some((arguments, _)) = enclosed(&decorator[name.len()..], '(', ')') {
+            for argument in split_top_level(arguments) {
+                match keyword_argument(&argument) {
+                    Some((key, value)) if key == "version" => version = string_value(&value),
+                    Some((key, value)) if key == "reason" || key == "message" => {
+                        message = string_value(&value)
+                    }
+                    Some(_) => (),
+                    None if message.is_none() => message = string_value(&argument),
+                    None => (),
+                }
+            }
+        }
+        Some(Self::new(DeprecationSource::Decorator, version, message))
+    }
+
+    /// Reads a `warnings.warn(...)` statement raising a deprecation warning.
+    pub fn from_warning(statement: &str) -> Option<Self> {
+        let statement = statement.trim();
+        let call = statement.split('(').next().unwrap_or(statement).trim();
+        if call != "warn" && call != "warnings.warn" {
+            return None;
+        }
+        let (arguments, _) = enclosed(&statement[call.len()..], '(', ')')?;
+        let arguments = split_top_level(arguments);
+        let category = arguments.iter().find_map(|argument| match keyword_argument(argument) {
+            Some((key, value)) if key == "category" => Some(value),
+            _ => None,
+        });
+        let category = category.or_else(|| arguments.get(1).filter(|a| keyword_argument(a).is_none()).cloned())?;
+        let category = category.rsplit('.').next().unwrap_or(&category);
+        if category != "DeprecationWarning" && category != "PendingDeprecationWarning" {
+            return None;
+        }
+        let message = arguments.first().and_then(|m| string_value(m));
+        Some(Self::new(DeprecationSource::Warning, None, message))
+    }
+
+    /// Reads the `.. deprecated:: version` directive of a cleaned docstring,
+    /// with the indented paragraph following it as message.
+    pub fn from_docstring(docstring: &str) -> Option<Self> {
+        let mut lines = docstring.lines().skip_while(|l| !l.trim_start().starts_with(".. deprecated::"));
+        let directive = lines.next()?;
+        let indent = directive.len() - directive.trim_start().len();
+        let version = directive.trim_start()[".. deprecated::".len()..].trim();
+        let message = lines
+            .take_while(|l| l.trim().is_empty() || l.len() - l.trim_start().len() > indent)
+            .map(|l| l.trim())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let message = message.trim();
+        Some(Self::new(
+            DeprecationSource::Directive,
+            (!version.is_empty()).then(|| version.to_string()),
+            (!message.is_empty()).then(|| message.to_string()),
+        ))
+    }
+
+    /// Completes the missing details with the ones of another declaration.
+    pub fn merge(mut self, other: Deprecation) -> Self {
+        self.version = self.version.or(other.version);
+        self.message = self.message.or(other.message);
+        self.replacement = self.replacement.or(other.replacement);
+        self.removal = self.removal.or(other.removal);
+        self
+    }
+
+    /// Short label shown next to deprecated symbols, e.g.
+    /// `deprecated since 1.2`.
+    pub fn badge(&self) -> String {
+        match &self.version {
+            Some(version) => format!("deprecated since {}", version),
+            None => String::from("deprecated"),
+        }
+    }
+}
+
+/// Returns the content of a string literal, without prefix and quotes.
+pub fn string_value(expression: &str) -> Option<String> {
+    let expression = expression.trim();
+    let start = expression.find(['"', '\''])?;
+    if !expression[..start].chars().all(|c| "rRuUfFbB".contains(c)) {
+        return None;
+    }
+    let literal = &expression[start..];
+    for quote in ["\"\"\"", "'''", "\"", "'"] {
+        if literal.len() >= 2 * quote.len() && literal.starts_with(quote) && literal.ends_with(quote) {
+            return Some(literal[quote.len()..literal.len() - quote.len()].to_string());
+        }
+    }
+    None
+}
+
+/// Returns the word captured by a phrase pattern in a sentence, without
+/// quotes, backticks and trailing punctuation.
+fn phrase_target(sentence: &str, pattern: &Regex) -> Option<String> {
+    let word = pattern.captures(sentence)?.get(1)?.as_str();
+    let word = word
+        .trim_matches(|c: char| "`'\":,;".contains(c))
+        .trim_end_matches(['.', ')'])
+        .trim_matches(|c: char| "`'\"".contains(c));
+    (!word.is_empty()).then(|| word.to_string())
+}
@@ -1 +1,3 @@
+pub mod config;
 pub mod context_engine;
+pub mod package;
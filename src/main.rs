@@ -0,0 +1,66 @@
+use std::env;
+use std::path::Path;
+use std::process::ExitCode;
+
+use chapter::package::{ApiDiff, Package, SearchIndex, VersionBump};
+
+const USAGE: &str = "usage: chapter diff <old_dir> <new_dir>
+       chapter search <query> [<source_dir> | <index.json>]";
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["diff", old, new] => diff(Path::new(old), Path::new(new)),
+        ["search", query] => search(query, Path::new(".")),
+        ["search", query, source] => search(query, Path::new(source)),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the API changes between two source trees and the version bump they
+/// need. Exits with status 1 when a change is breaking.
+fn diff(old: &Path, new: &Path) -> ExitCode {
+    let diff = match ApiDiff::between_dirs(old, new) {
+        Ok(diff) => diff,
+        Err(error) => {
+            eprintln!("chapter: {}", error);
+            return ExitCode::from(2);
+        }
+    };
+    print!("{}", diff);
+    let bump = match diff.version_bump() {
+        VersionBump::Major => "major",
+        VersionBump::Minor => "minor",
+        VersionBump::Patch => "patch",
+    };
+    println!("Version bump: {}", bump);
+    if diff.is_breaking() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Prints the symbols matching a query, best first. The index is read from a
+/// JSON file or built from a source directory.
+fn search(query: &str, source: &Path) -> ExitCode {
+    let index = if source.is_dir() {
+        Package::from_dir(source).map(|package| SearchIndex::from_package(&package))
+    } else {
+        SearchIndex::read(source)
+    };
+    let index = match index {
+        Ok(index) => index,
+        Err(error) => {
+            eprintln!("chapter: {}: {}", source.display(), error);
+            return ExitCode::from(2);
+        }
+    };
+    for hit in index.search(query) {
+        println!("{}", hit);
+    }
+    ExitCode::SUCCESS
+}
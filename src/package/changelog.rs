@@ -0,0 +1,202 @@
+//! Release notes written from API diffs.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use super::diff::{ApiChange, ApiDiff, ChangeKind};
+
+/// Changelog sections, in the order they are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Section {
+    Breaking,
+    Feature,
+    Deprecation,
+    Misc,
+}
+
+impl Section {
+    /// Heading of the section in Markdown changelogs.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Breaking => "Breaking changes",
+            Self::Feature => "New APIs",
+            Self::Deprecation => "Deprecations",
+            Self::Misc => "Other changes",
+        }
+    }
+
+    /// Fragment type used in towncrier file names, one of the types
+    /// towncrier knows without configuration. Breaking changes and
+    /// deprecations both go to `removal`, titled "Deprecations and Removals".
+    pub fn fragment_type(&self) -> &'static str {
+        match self {
+            Self::Breaking => "removal",
+            Self::Feature => "feature",
+            Self::Deprecation => "removal",
+            Self::Misc => "misc",
+        }
+    }
+
+    pub fn of(change: &ApiChange) -> Self {
+        match change.kind {
+            _ if change.breaking => Self::Breaking,
+            ChangeKind::Added => Self::Feature,
+            ChangeKind::Deprecated => Self::Deprecation,
+            _ => Self::Misc,
+        }
+    }
+}
+
+/// A towncrier news fragment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    /// Orphan fragment name, e.g. `+sdk.Client.get.removal.md`
+    pub file_name: String,
+    pub content: String,
+}
+
+/// Writes the changes of an `ApiDiff` as a Markdown changelog section or as
+/// towncrier fragments.
+///
+/// Symbols link to their documentation when a URL template is given. The
+/// template may use `{symbol}` for the qualified name and `{module}` for the
+/// module of the symbol. Removed symbols are not linked.
+///
+/// ```
+/// use chapter::package::{ApiDiff, ChangelogWriter, Module, Package};
+///
+/// let mut old = Package::new();
+/// old.add_module(Module::parse("sdk", "def connect(host):\n    pass\n"));
+/// let mut new = Package::new();
+/// new.add_module(Module::parse("sdk", "def connect(host, port):\n    pass\n"));
+///
+/// let writer = ChangelogWriter::new("2.0.0").doc_url("https://docs.example.com/{module}.html#{symbol}");
+/// let markdown = writer.markdown(&ApiDiff::compare(&old, &new));
+/// assert!(markdown.contains("- [`sdk.connect`](https://docs.example.com/sdk.html#sdk.connect): required parameter `port` added\n"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChangelogWriter {
+    version: String,
+    date: Option<String>,
+    doc_url: Option<String>,
+
+    /// Fragment types replacing the defaults of `Section::fragment_type`
+    fragment_types: BTreeMap<Section, String>,
+}
+
+impl ChangelogWriter {
+    pub fn new(version: &str) -> Self {
+        Self {
+            version: version.to_string(),
+            date: None,
+            doc_url: None,
+            fragment_types: BTreeMap::new(),
+        }
+    }
+
+    /// Release date written next to the version.
+    pub fn date(mut self, date: &str) -> Self {
+        self.date = Some(date.to_string());
+        self
+    }
+
+    /// URL template of the documentation of a symbol.
+    pub fn doc_url(mut self, template: &str) -> Self {
+        self.doc_url = Some(template.to_string());
+        self
+    }
+
+    /// Sets the fragment type of a section, for projects declaring their own
+    /// types in `[tool.towncrier.type]`.
+    pub fn fragment_type(mut self, section: Section, fragment_type: &str) -> Self {
+        self.fragment_types.insert(section, fragment_type.to_string());
+        self
+    }
+
+    /// Writes the changelog section of the release.
+    pub fn markdown(&self, diff: &ApiDiff) -> String {
+        let mut text = match &self.date {
+            Some(date) => format!("## {} ({})\n", self.version, date),
+            None => format!("## {}\n", self.version),
+        };
+        let sections = sections(diff);
+        if sections.is_empty() {
+            text.push_str("\nNo changes to the public API.\n");
+        }
+        for (section, changes) in sections {
+            text.push_str(&format!("\n### {}\n\n", section.title()));
+            for (symbol, descriptions) in changes {
+                text.push_str(&format!("- {}: {}\n", self.link(symbol), descriptions.join("; ")));
+            }
+        }
+        text
+    }
+
+    /// Creates one fragment per symbol and section. Sections sharing a
+    /// fragment type get a counter in the name, e.g.
+    /// `+sdk.get.removal.1.md`.
+    pub fn fragments(&self, diff: &ApiDiff) -> Vec<Fragment> {
+        let mut fragments: Vec<Fragment> = Vec::new();
+        for (section, changes) in sections(diff) {
+            let fragment_type = self
+                .fragment_types
+                .get(&section)
+                .map(String::as_str)
+                .unwrap_or(section.fragment_type());
+            for (symbol, descriptions) in changes {
+                let mut file_name = format!("+{}.{}.md", symbol.symbol, fragment_type);
+                let mut counter = 0;
+                while fragments.iter().any(|f| f.file_name == file_name) {
+                    counter += 1;
+                    file_name = format!("+{}.{}.{}.md", symbol.symbol, fragment_type, counter);
+                }
+                fragments.push(Fragment {
+                    file_name,
+                    content: format!("{}: {}\n", self.link(symbol), descriptions.join("; ")),
+                });
+            }
+        }
+        fragments
+    }
+
+    /// Writes the fragments to a towncrier news directory and returns their
+    /// paths.
+    pub fn write_fragments(&self, diff: &ApiDiff, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(path)?;
+        let mut written = Vec::new();
+        for fragment in self.fragments(diff) {
+            let file = path.join(&fragment.file_name);
+            fs::write(&file, fragment.content)?;
+            written.push(file);
+        }
+        Ok(written)
+    }
+
+    fn link(&self, change: &ApiChange) -> String {
+        match &self.doc_url {
+            Some(template) if change.kind != ChangeKind::Removed => format!(
+                "[`{}`]({})",
+                change.symbol,
+                template
+                    .replace("{symbol}", &change.symbol)
+                    .replace("{module}", &change.module)
+            ),
+            _ => format!("`{}`", change.symbol),
+        }
+    }
+}
+
+type SectionChanges<'a> = Vec<(&'a ApiChange, Vec<&'a str>)>;
+
+/// Groups the changes by section, then by symbol in the order of the diff.
+fn sections(diff: &ApiDiff) -> BTreeMap<Section, SectionChanges<'_>> {
+    let mut sections: BTreeMap<Section, SectionChanges<'_>> = BTreeMap::new();
+    for change in diff.changes.iter() {
+        let changes = sections.entry(Section::of(change)).or_default();
+        match changes.iter_mut().find(|(c, _)| c.symbol == change.symbol) {
+            Some((_, descriptions)) => descriptions.push(&change.description),
+            None => changes.push((change, vec![&change.description])),
+        }
+    }
+    sections
+}
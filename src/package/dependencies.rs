@@ -0,0 +1,156 @@
+//! Import dependencies between the modules of a package.
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::Package;
+
+/// Directed graph of the imports between modules of the same package.
+///
+/// Imports of modules outside of the package are not recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyGraph {
+    /// Modules imported by every module of the package
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl DependencyGraph {
+    /// Returns the modules imported by a module.
+    pub fn dependencies(&self, module: &str) -> Option<&BTreeSet<String>> {
+        self.edges.get(module)
+    }
+
+    /// Finds the import cycles of the graph as groups of modules importing
+    /// each other, directly or through other modules of the group.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: 0,
+            indices: BTreeMap::new(),
+            low_links: BTreeMap::new(),
+            stack: Vec::new(),
+            components: Vec::new(),
+        };
+        for module in self.edges.keys() {
+            if !tarjan.indices.contains_key(module.as_str()) {
+                tarjan.visit(module);
+            }
+        }
+
+        tarjan
+            .components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1
+                    || self
+                        .edges
+                        .get(&component[0])
+                        .is_some_and(|d| d.contains(&component[0]))
+            })
+            .map(|mut component| {
+                component.sort();
+                component
+            })
+            .collect()
+    }
+
+    /// Exports the graph in the Graphviz DOT format, drawing the edges that
+    /// are part of a cycle in red.
+    pub fn to_dot(&self) -> String {
+        let cycles = self.cycles();
+        let in_cycle = |from: &str, to: &str| {
+            cycles
+                .iter()
+                .any(|c| c.iter().any(|m| m == from) && c.iter().any(|m| m == to))
+        };
+
+        let mut dot = String::from("digraph dependencies {\n");
+        for module in self.edges.keys() {
+            dot.push_str(&format!("    \"{}\";\n", module));
+        }
+        for (module, dependencies) in &self.edges {
+            for dependency in dependencies {
+                let style = if in_cycle(module, dependency) { " [color=red]" } else { "" };
+                dot.push_str(&format!("    \"{}\" -> \"{}\"{};\n", module, dependency, style));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// State of Tarjan's strongly connected components algorithm.
+struct Tarjan<'a> {
+    graph: &'a DependencyGraph,
+    index: usize,
+    indices: BTreeMap<&'a str, usize>,
+    low_links: BTreeMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    components: Vec<Vec<String>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, module: &'a str) {
+        self.indices.insert(module, self.index);
+        self.low_links.insert(module, self.index);
+        self.index += 1;
+        self.stack.push(module);
+
+        for dependency in self.graph.edges.get(module).into_iter().flatten() {
+            let dependency = dependency.as_str();
+            if !self.indices.contains_key(dependency) {
+                self.visit(dependency);
+                let low = self.low_links[module].min(self.low_links[dependency]);
+                self.low_links.insert(module, low);
+            } else if self.stack.contains(&dependency) {
+                let low = self.low_links[module].min(self.indices[dependency]);
+                self.low_links.insert(module, low);
+            }
+        }
+
+        if self.low_links[module] == self.indices[module] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                component.push(member.to_string());
+                if member == module {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+impl Package {
+    /// Builds the import graph of the package. Imports under
+    /// `if TYPE_CHECKING:` never run, so they are only followed when
+    /// `type_checking` is set.
+    pub fn dependency_graph(&self, type_checking: bool) -> DependencyGraph {
+        let mut graph = DependencyGraph::default();
+        for module in self.modules.values() {
+            let dependencies = graph.edges.entry(module.name.clone()).or_default();
+            for import in module.imports() {
+                if import.type_checking && !type_checking {
+                    continue;
+                }
+                let imported = module.imported_module(&import);
+                if !import.is_from {
+                    for name in &import.names {
+                        if let Some((target, _)) = self.split_qualified(&name.name) {
+                            dependencies.insert(target.name.clone());
+                        }
+                    }
+                    continue;
+                }
+                for name in &import.names {
+                    // `from package import module` depends on the submodule
+                    let submodule = format!("{}.{}", imported, name.name);
+                    if self.modules.contains_key(&submodule) {
+                        dependencies.insert(submodule);
+                    } else if let Some((target, _)) = self.split_qualified(&imported) {
+                        dependencies.insert(target.name.clone());
+                    }
+                }
+            }
+        }
+        graph
+    }
+}
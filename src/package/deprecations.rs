@@ -0,0 +1,133 @@
+//! Deprecated symbols of a package.
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+use crate::context_engine::syntax::Deprecation;
+
+use super::Package;
+
+/// Deprecated symbol and where it is defined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedSymbol {
+    /// Qualified name, e.g. `pkg.module.Class.method`
+    pub symbol: String,
+    pub context_type: ContextType,
+
+    /// Zero based line of the definition
+    pub line: Option<usize>,
+    pub deprecation: Deprecation,
+}
+
+/// Deprecated symbols of a package, sorted by planned removal version and
+/// then by name. Symbols without removal version come last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeprecationReport {
+    pub symbols: Vec<DeprecatedSymbol>,
+}
+
+impl DeprecationReport {
+    /// Returns the symbols planned for removal in the given version or an
+    /// earlier one.
+    pub fn due_for_removal(&self, version: &str) -> Vec<&DeprecatedSymbol> {
+        self.symbols
+            .iter()
+            .filter(|s| {
+                s.deprecation
+                    .removal
+                    .as_deref()
+                    .is_some_and(|removal| compare_versions(removal, version) != Ordering::Greater)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for DeprecationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for symbol in self.symbols.iter() {
+            let deprecation = &symbol.deprecation;
+            write!(f, "{}: {}", symbol.symbol, deprecation.badge())?;
+            if let Some(removal) = &deprecation.removal {
+                write!(f, ", removal in {}", removal)?;
+            }
+            if let Some(replacement) = &deprecation.replacement {
+                write!(f, ", use `{}`", replacement)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Package {
+    /// Lists the deprecated symbols defined at the top level of the modules
+    /// or inside their classes.
+    pub fn deprecations(&self) -> DeprecationReport {
+        let mut symbols = Vec::new();
+        for module in self.modules.values() {
+            collect_deprecations(&module.root, &module.name, &mut symbols);
+        }
+        symbols.sort_by(|a, b| {
+            let removal = match (&a.deprecation.removal, &b.deprecation.removal) {
+                (Some(a), Some(b)) => compare_versions(a, b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            removal.then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        DeprecationReport { symbols }
+    }
+}
+
+fn collect_deprecations(
+    node: &Rc<RefCell<ContextNode>>,
+    prefix: &str,
+    symbols: &mut Vec<DeprecatedSymbol>,
+) {
+    for child in node.borrow().children.iter() {
+        let (symbol, is_class) = {
+            let child = child.borrow();
+            if child.context_type == ContextType::DOCSTRING {
+                continue;
+            }
+            let symbol = format!("{}.{}", prefix, child.identifier());
+            if let Some(deprecation) = &child.deprecation {
+                symbols.push(DeprecatedSymbol {
+                    symbol: symbol.clone(),
+                    context_type: child.context_type.clone(),
+                    line: child.span.start_line,
+                    deprecation: deprecation.clone(),
+                });
+            }
+            (symbol, child.context_type == ContextType::CLASS)
+        };
+        if is_class {
+            collect_deprecations(child, &symbol, symbols);
+        }
+    }
+}
+
+/// Compares dotted versions component by component, numerically when both
+/// components are numbers.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.trim_start_matches('v').split('.');
+    let mut b_parts = b.trim_start_matches('v').split('.');
+    loop {
+        let ordering = match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (a, b) => {
+                let (a, b) = (a.unwrap_or("0"), b.unwrap_or("0"));
+                match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => a.cmp(b),
+                }
+            }
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
@@ -0,0 +1,532 @@
+//! Public API differences between two versions of a package.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+use crate::context_engine::syntax::{FunctionSignature, Parameter, ParameterKind};
+
+use super::{is_public_name, Module, Package};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+    Deprecated,
+}
+
+/// Change of one public symbol between two versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiChange {
+    pub kind: ChangeKind,
+
+    /// Module holding the symbol
+    pub module: String,
+
+    /// Qualified name of the symbol, e.g. `pkg.module.Class.method`
+    pub symbol: String,
+
+    /// Type of the symbol in the newest version holding it, `ROOT` for
+    /// modules
+    pub context_type: ContextType,
+
+    /// Whether code written against the old version may break
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.symbol, self.description)
+    }
+}
+
+/// Version component to increase for a release, following semver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// Changes of the public API of a package, sorted by symbol.
+///
+/// A module is public when none of its name parts starts with an
+/// underscore, except for dunder names such as `__main__`. Inside a module, `__all__` lists the public top level names
+/// when present; otherwise names without a leading underscore are public.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiDiff {
+    pub changes: Vec<ApiChange>,
+}
+
+impl ApiDiff {
+    /// Compares the public API of two versions of a package.
+    pub fn compare(old: &Package, new: &Package) -> Self {
+        let mut diff = Self::default();
+        let modules = old
+            .modules
+            .keys()
+            .chain(new.modules.keys())
+            .filter(|name| is_public_name(name))
+            .collect::<BTreeSet<_>>();
+
+        for name in modules {
+            match (old.module(name), new.module(name)) {
+                (Some(_), None) => {
+                    diff.push(ChangeKind::Removed, name, name, ContextType::ROOT, true, "module removed")
+                }
+                (None, Some(_)) => {
+                    diff.push(ChangeKind::Added, name, name, ContextType::ROOT, false, "module added")
+                }
+                (Some(old), Some(new)) => diff.compare_modules(old, new),
+                (None, None) => (),
+            }
+        }
+        diff.changes.sort_by(|a, b| a.symbol.cmp(&b.symbol).then(a.kind.cmp(&b.kind)));
+        diff
+    }
+
+    /// Reads the packages found in two directories and compares them.
+    pub fn between_dirs(old: &Path, new: &Path) -> io::Result<Self> {
+        Ok(Self::compare(&Package::from_dir(old)?, &Package::from_dir(new)?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn breaking(&self) -> impl Iterator<Item = &ApiChange> {
+        self.changes.iter().filter(|c| c.breaking)
+    }
+
+    pub fn non_breaking(&self) -> impl Iterator<Item = &ApiChange> {
+        self.changes.iter().filter(|c| !c.breaking)
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.breaking().next().is_some()
+    }
+
+    /// Returns the smallest version increase the changes require: major for
+    /// breaking changes, minor for additions and deprecations and patch
+    /// otherwise.
+    pub fn version_bump(&self) -> VersionBump {
+        if self.is_breaking() {
+            VersionBump::Major
+        } else if self.changes.iter().any(|c| matches!(c.kind, ChangeKind::Added | ChangeKind::Deprecated)) {
+            VersionBump::Minor
+        } else {
+            VersionBump::Patch
+        }
+    }
+
+    fn push(
+        &mut self,
+        kind: ChangeKind,
+        module: &str,
+        symbol: &str,
+        context_type: ContextType,
+        breaking: bool,
+        description: &str,
+    ) {
+        self.changes.push(ApiChange {
+            kind,
+            module: module.to_string(),
+            symbol: symbol.to_string(),
+            context_type,
+            breaking,
+            description: description.to_string(),
+        });
+    }
+
+    fn compare_modules(&mut self, old: &Module, new: &Module) {
+        let old_symbols = symbols(old);
+        let new_symbols = symbols(new);
+        let qualified = |path: &str| format!("{}.{}", new.name, path);
+        // Members of removed or added classes are covered by their class
+        let mut removed: Vec<&str> = Vec::new();
+        let mut added: Vec<&str> = Vec::new();
+        let is_member = |path: &str, classes: &[&str]| {
+            classes.iter().any(|class| path.strip_prefix(class).is_some_and(|rest| rest.starts_with('.')))
+        };
+
+        for (path, old_symbol) in old_symbols.iter() {
+            let old_type = old_symbol.node.borrow().context_type.clone();
+            if is_member(path, &removed) || is_member(path, &added) {
+                continue;
+            }
+            let new_symbol = match new_symbols.get(path) {
+                Some(symbol) => symbol,
+                None if old_symbol.public => {
+                    let description = format!("{} removed", describe(&old_type));
+                    self.push(ChangeKind::Removed, &new.name, &qualified(path), old_type, true, &description);
+                    removed.push(path);
+                    continue;
+                }
+                None => continue,
+            };
+            let new_type = new_symbol.node.borrow().context_type.clone();
+            match (old_symbol.public, new_symbol.public) {
+                (true, false) => {
+                    let description = format!("{} is no longer public", describe(&new_type));
+                    self.push(ChangeKind::Removed, &new.name, &qualified(path), new_type, true, &description);
+                    removed.push(path);
+                }
+                (false, true) => {
+                    let description = format!("{} became public", describe(&new_type));
+                    self.push(ChangeKind::Added, &new.name, &qualified(path), new_type, false, &description);
+                    added.push(path);
+                }
+                (true, true) => {
+                    if new_symbol.node.borrow().deprecation.is_some()
+                        && old_symbol.node.borrow().deprecation.is_none()
+                    {
+                        let description = format!("{} deprecated", describe(&new_type));
+                        self.push(ChangeKind::Deprecated, &new.name, &qualified(path), new_type.clone(), false, &description);
+                    }
+                    for (breaking, description) in compare_nodes(old_symbol, new_symbol) {
+                        self.push(
+                            ChangeKind::Changed,
+                            &new.name,
+                            &qualified(path),
+                            new_type.clone(),
+                            breaking,
+                            &description,
+                        );
+                    }
+                }
+                (false, false) => (),
+            }
+        }
+
+        for (path, new_symbol) in new_symbols.iter() {
+            if new_symbol.public && !old_symbols.contains_key(path) && !is_member(path, &added) {
+                let new_type = new_symbol.node.borrow().context_type.clone();
+                let description = format!("{} added", describe(&new_type));
+                self.push(ChangeKind::Added, &new.name, &qualified(path), new_type, false, &description);
+                added.push(path);
+            }
+        }
+
+        // Names exported without being defined in the module, e.g. imports
+        let old_all = old.exported_names().unwrap_or_default();
+        let new_all = new.exported_names().unwrap_or_default();
+        for name in old_all.iter().filter(|n| !new_all.contains(n)) {
+            if !old_symbols.contains_key(name) && !new_symbols.contains_key(name) {
+                let all = qualified("__all__");
+                let description = format!("`{}` removed from `__all__`", name);
+                self.push(ChangeKind::Changed, &new.name, &all, ContextType::ALL, true, &description);
+            }
+        }
+        for name in new_all.iter().filter(|n| !old_all.contains(n)) {
+            if !old_symbols.contains_key(name) && !new_symbols.contains_key(name) {
+                let all = qualified("__all__");
+                let description = format!("`{}` added to `__all__`", name);
+                self.push(ChangeKind::Changed, &new.name, &all, ContextType::ALL, false, &description);
+            }
+        }
+    }
+}
+
+impl fmt::Display for ApiDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let breaking = self.breaking().collect::<Vec<_>>();
+        let non_breaking = self.non_breaking().collect::<Vec<_>>();
+        if !breaking.is_empty() {
+            writeln!(f, "Breaking changes:")?;
+            for change in breaking {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        if !non_breaking.is_empty() {
+            writeln!(f, "Non-breaking changes:")?;
+            for change in non_breaking {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Symbol of a module, found at the top level or inside a class body.
+struct Symbol {
+    node: Rc<RefCell<ContextNode>>,
+    public: bool,
+}
+
+/// Collects the symbols of a module by their path inside it. Only the first
+/// of several conditional definitions is kept.
+fn symbols(module: &Module) -> BTreeMap<String, Symbol> {
+    let mut symbols = BTreeMap::new();
+    let exported = module.exported_names();
+    for child in module.root.borrow().children.iter() {
+        let node = child.borrow();
+        let public = match &exported {
+            Some(names) => names.iter().any(|name| name == node.identifier()),
+            None => node.is_public,
+        };
+        collect_symbols(child, "", public, &mut symbols);
+    }
+    symbols
+}
+
+fn collect_symbols(
+    node: &Rc<RefCell<ContextNode>>,
+    prefix: &str,
+    public: bool,
+    symbols: &mut BTreeMap<String, Symbol>,
+) {
+    let (path, is_class) = {
+        let node = node.borrow();
+        let tracked = matches!(
+            node.context_type,
+            ContextType::CLASS
+                | ContextType::METHOD
+                | ContextType::PROPERTY
+                | ContextType::VARIABLE
+                | ContextType::ALIAS
+                | ContextType::ATTRIBUTE
+        );
+        if !tracked {
+            return;
+        }
+        (
+            format!("{}{}", prefix, node.identifier()),
+            node.context_type == ContextType::CLASS,
+        )
+    };
+    if symbols.contains_key(&path) {
+        return;
+    }
+    symbols.insert(
+        path.clone(),
+        Symbol {
+            node: Rc::clone(node),
+            public,
+        },
+    );
+
+    if is_class {
+        for child in node.borrow().children.iter() {
+            let child_public = public && child.borrow().is_public;
+            collect_symbols(child, &format!("{}.", path), child_public, symbols);
+        }
+    }
+}
+
+fn describe(context_type: &ContextType) -> &'static str {
+    match context_type {
+        ContextType::ROOT => "module",
+        ContextType::CLASS => "class",
+        ContextType::METHOD => "function",
+        ContextType::PROPERTY => "property",
+        ContextType::ATTRIBUTE => "attribute",
+        ContextType::ALIAS => "type alias",
+        _ => "variable",
+    }
+}
+
+/// Lists the changes between two versions of a symbol, with whether each
+/// of them is breaking.
+fn compare_nodes(old: &Symbol, new: &Symbol) -> Vec<(bool, String)> {
+    let old = old.node.borrow();
+    let new = new.node.borrow();
+    let mut changes = Vec::new();
+    if old.context_type != new.context_type {
+        changes.push((
+            true,
+            format!(
+                "changed from {} to {}",
+                describe(&old.context_type),
+                describe(&new.context_type)
+            ),
+        ));
+        return changes;
+    }
+
+    if let (Some(old_header), Some(new_header)) = (&old.class_header, &new.class_header) {
+        for base in old_header.bases.iter().filter(|b| !new_header.bases.contains(b)) {
+            changes.push((true, format!("base `{}` removed", base)));
+        }
+        for base in new_header.bases.iter().filter(|b| !old_header.bases.contains(b)) {
+            changes.push((false, format!("base `{}` added", base)));
+        }
+    }
+
+    if let (Some(old_signature), Some(new_signature)) = (&old.signature, &new.signature) {
+        changes.extend(compare_signatures(old_signature, new_signature));
+    }
+    if old.overloads != new.overloads && !(old.overloads.is_empty() && new.overloads.is_empty()) {
+        changes.push((false, String::from("overloads changed")));
+    }
+
+    if let (Some(old_property), Some(new_property)) = (&old.property, &new.property) {
+        let accessors = [
+            (old_property.writable, new_property.writable, "setter"),
+            (old_property.deletable, new_property.deletable, "deleter"),
+        ];
+        for (old_accessor, new_accessor, name) in accessors {
+            match (old_accessor, new_accessor) {
+                (true, false) => changes.push((true, format!("{} removed", name))),
+                (false, true) => changes.push((false, format!("{} added", name))),
+                _ => (),
+            }
+        }
+        if old_property.annotation != new_property.annotation {
+            changes.push((false, type_change(&old_property.annotation, &new_property.annotation)));
+        }
+    }
+
+    let annotation = |node: &ContextNode| node.variable.as_ref().and_then(|v| v.annotation.clone());
+    if old.variable.is_some() && new.variable.is_some() && annotation(&old) != annotation(&new) {
+        changes.push((false, type_change(&annotation(&old), &annotation(&new))));
+    }
+    changes
+}
+
+fn type_change(old: &Option<String>, new: &Option<String>) -> String {
+    let show = |annotation: &Option<String>| match annotation {
+        Some(annotation) => format!("`{}`", annotation),
+        None => String::from("none"),
+    };
+    format!("type changed from {} to {}", show(old), show(new))
+}
+
+fn is_positional(parameter: &Parameter) -> bool {
+    matches!(
+        parameter.kind,
+        ParameterKind::PositionalOnly | ParameterKind::PositionalOrKeyword
+    )
+}
+
+/// Compares two signatures of a function, parameter by parameter.
+fn compare_signatures(old: &FunctionSignature, new: &FunctionSignature) -> Vec<(bool, String)> {
+    let mut changes = Vec::new();
+    if old.is_async != new.is_async {
+        let kind = if new.is_async { "asynchronous" } else { "synchronous" };
+        changes.push((true, format!("became {}", kind)));
+    }
+
+    let variadic = |signature: &FunctionSignature, kind: ParameterKind| {
+        signature.parameters.iter().find(|p| p.kind == kind).map(|p| p.name.clone())
+    };
+    for kind in [ParameterKind::VarPositional, ParameterKind::VarKeyword] {
+        let prefix = if kind == ParameterKind::VarPositional { "*" } else { "**" };
+        match (variadic(old, kind), variadic(new, kind)) {
+            (Some(name), None) => changes.push((true, format!("parameter `{}{}` removed", prefix, name))),
+            (None, Some(name)) => changes.push((false, format!("parameter `{}{}` added", prefix, name))),
+            _ => (),
+        }
+    }
+
+    let named = |signature: &FunctionSignature| {
+        signature
+            .parameters
+            .iter()
+            .filter(|p| !matches!(p.kind, ParameterKind::VarPositional | ParameterKind::VarKeyword))
+            .cloned()
+            .collect::<Vec<Parameter>>()
+    };
+    let old_parameters = named(old);
+    let new_parameters = named(new);
+    // Positions among the parameters both versions have, so that a removed
+    // parameter does not also report the following ones as moved
+    let position = |parameters: &[Parameter], others: &[Parameter], name: &str| {
+        parameters
+            .iter()
+            .filter(|p| is_positional(p) && others.iter().any(|o| o.name == p.name))
+            .position(|p| p.name == name)
+    };
+    let accepts_keywords = variadic(new, ParameterKind::VarKeyword).is_some();
+    let accepts_positional = variadic(new, ParameterKind::VarPositional).is_some();
+
+    for parameter in old_parameters.iter() {
+        let new_parameter = match new_parameters.iter().find(|p| p.name == parameter.name) {
+            Some(found) => found,
+            None => {
+                let absorbed = match parameter.kind {
+                    ParameterKind::PositionalOnly => accepts_positional,
+                    ParameterKind::KeywordOnly => accepts_keywords,
+                    _ => accepts_positional && accepts_keywords,
+                };
+                changes.push((!absorbed, format!("parameter `{}` removed", parameter.name)));
+                continue;
+            }
+        };
+
+        let name = &parameter.name;
+        match (parameter.kind, new_parameter.kind) {
+            (old_kind, new_kind) if old_kind == new_kind => (),
+            (_, ParameterKind::KeywordOnly) => {
+                changes.push((true, format!("parameter `{}` became keyword-only", name)))
+            }
+            (_, ParameterKind::PositionalOnly) => {
+                changes.push((true, format!("parameter `{}` became positional-only", name)))
+            }
+            (_, _) => changes.push((false, format!("parameter `{}` accepts more call styles", name))),
+        }
+        let old_position = position(&old_parameters, &new_parameters, name);
+        let new_position = position(&new_parameters, &old_parameters, name);
+        if let (Some(old_position), Some(new_position)) = (old_position, new_position) {
+            if old_position != new_position {
+                changes.push((
+                    true,
+                    format!(
+                        "parameter `{}` moved from position {} to {}",
+                        name, old_position, new_position
+                    ),
+                ));
+            }
+        }
+
+        match (&parameter.default, &new_parameter.default) {
+            (Some(_), None) => changes.push((true, format!("parameter `{}` became required", name))),
+            (None, Some(default)) => {
+                changes.push((false, format!("parameter `{}` got the default `{}`", name, default)))
+            }
+            (Some(old_default), Some(new_default)) if old_default != new_default => changes.push((
+                false,
+                format!(
+                    "default of parameter `{}` changed from `{}` to `{}`",
+                    name, old_default, new_default
+                ),
+            )),
+            _ => (),
+        }
+        if parameter.annotation != new_parameter.annotation {
+            changes.push((
+                false,
+                format!(
+                    "parameter `{}` {}",
+                    name,
+                    type_change(&parameter.annotation, &new_parameter.annotation)
+                ),
+            ));
+        }
+    }
+
+    for parameter in new_parameters.iter() {
+        if old_parameters.iter().any(|p| p.name == parameter.name) {
+            continue;
+        }
+        match parameter.default {
+            Some(_) => changes.push((false, format!("optional parameter `{}` added", parameter.name))),
+            None => changes.push((true, format!("required parameter `{}` added", parameter.name))),
+        }
+    }
+
+    if old.return_annotation != new.return_annotation {
+        changes.push((
+            false,
+            format!(
+                "return {}",
+                type_change(&old.return_annotation, &new.return_annotation)
+            ),
+        ));
+    }
+    changes
+}
@@ -0,0 +1,360 @@
+//! Code examples found in docstrings, written out as pytest tests.
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::{fs, io};
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+use crate::context_engine::rules::DocstringStyle;
+
+use super::{Module, Package};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExampleKind {
+    /// `>>>` prompts with their expected output
+    Doctest,
+
+    /// Fenced python block, `.. code-block:: python` or `::` literal block
+    /// of an `Example:` section
+    Code,
+}
+
+/// Statement of a doctest, with the output it is expected to print.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctestStep {
+    pub source: String,
+    pub expected: String,
+
+    /// Zero based source line of the `>>>` prompt
+    pub line: usize,
+}
+
+/// Example extracted from a docstring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    /// Module defining the documented symbol
+    pub module: String,
+
+    /// Qualified name of the documented symbol, e.g. `pkg.mod.Class.method`
+    pub qualname: String,
+    pub kind: ExampleKind,
+
+    /// Zero based source line where the example starts
+    pub line: usize,
+
+    /// Code of `Code` examples, sources of the steps of doctests
+    pub code: String,
+
+    /// Statements of doctests, empty for `Code` examples
+    pub steps: Vec<DoctestStep>,
+}
+
+impl Module {
+    /// Extracts the examples of every docstring of the module. All the
+    /// doctest prompts of a docstring form a single example, sharing their
+    /// namespace like the `doctest` module does.
+    pub fn examples(&self) -> Vec<Example> {
+        let mut examples = Vec::new();
+        collect_examples(&self.root, &self.name, &self.name, self.docstring_style, &mut examples);
+        examples
+    }
+}
+
+impl Package {
+    pub fn examples(&self) -> Vec<Example> {
+        self.modules.values().flat_map(|m| m.examples()).collect()
+    }
+}
+
+/// Example found in a cleaned docstring, with lines counted from the start
+/// of the docstring.
+struct Found {
+    kind: ExampleKind,
+    line: usize,
+    code: Vec<String>,
+    steps: Vec<(String, String, usize)>,
+}
+
+fn collect_examples(
+    node: &Rc<RefCell<ContextNode>>,
+    module: &str,
+    qualname: &str,
+    style: DocstringStyle,
+    examples: &mut Vec<Example>,
+) {
+    let node = node.borrow();
+    let docstring = node
+        .children
+        .iter()
+        .find(|c| c.borrow().context_type == ContextType::DOCSTRING);
+    if let (Some(docstring), Some(text)) = (docstring, node.docstring()) {
+        let docstring = docstring.borrow();
+        let start = docstring.span.start_line.unwrap_or(0)
+            + docstring.value.as_deref().map(leading_blank_lines).unwrap_or(0);
+        for found in find_examples(&text, style) {
+            let steps = found
+                .steps
+                .into_iter()
+                .map(|(source, expected, line)| DoctestStep {
+                    source,
+                    expected,
+                    line: start + line,
+                })
+                .collect::<Vec<_>>();
+            let code = match found.kind {
+                ExampleKind::Doctest => steps.iter().map(|s| s.source.as_str()).collect::<Vec<_>>().join("\n"),
+                ExampleKind::Code => found.code.join("\n"),
+            };
+            examples.push(Example {
+                module: module.to_string(),
+                qualname: qualname.to_string(),
+                kind: found.kind,
+                line: start + found.line,
+                code,
+                steps,
+            });
+        }
+    }
+
+    for child in node.children.iter() {
+        if child.borrow().context_type == ContextType::DOCSTRING {
+            continue;
+        }
+        let name = format!("{}.{}", qualname, child.borrow().identifier());
+        collect_examples(child, module, &name, style, examples);
+    }
+}
+
+/// Counts the blank lines `clean_docstring` drops at the start of a raw
+/// docstring, so that lines of the cleaned text map back to the source.
+pub(super) fn leading_blank_lines(raw: &str) -> usize {
+    let text = raw.trim_start().trim_start_matches(['r', 'R', 'u', 'U']);
+    let text = ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find_map(|q| text.strip_prefix(q))
+        .unwrap_or(text);
+    text.lines().take_while(|l| l.trim().is_empty()).count()
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Finds the doctest prompts and the code blocks of a cleaned docstring.
+/// `Example:` sections only exist in Google style docstrings.
+fn find_examples(docstring: &str, style: DocstringStyle) -> Vec<Found> {
+    let google = matches!(style, DocstringStyle::Auto | DocstringStyle::Google);
+    let lines = docstring.lines().collect::<Vec<&str>>();
+    let mut examples = Vec::new();
+    let mut doctest: Option<Found> = None;
+    // Indentation of the header of the `Example:` section being read
+    let mut section: Option<usize> = None;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && section.is_some_and(|margin| indentation(line) <= margin) {
+            section = None;
+        }
+
+        if trimmed == ">>>" || trimmed.starts_with(">>> ") {
+            let margin = indentation(line);
+            let mut source = vec![trimmed.trim_start_matches(">>>").trim_start().to_string()];
+            let prompt_line = index;
+            index += 1;
+            while index < lines.len() {
+                let trimmed = lines[index].trim();
+                if trimmed != "..." && !trimmed.starts_with("... ") {
+                    break;
+                }
+                source.push(lines[index].get(margin + 4..).unwrap_or("").to_string());
+                index += 1;
+            }
+            let mut expected = Vec::new();
+            while index < lines.len() {
+                let trimmed = lines[index].trim();
+                if trimmed.is_empty() || trimmed == ">>>" || trimmed.starts_with(">>> ") {
+                    break;
+                }
+                expected.push(lines[index].get(margin..).unwrap_or(trimmed));
+                index += 1;
+            }
+            let found = doctest.get_or_insert_with(|| Found {
+                kind: ExampleKind::Doctest,
+                line: prompt_line,
+                code: Vec::new(),
+                steps: Vec::new(),
+            });
+            found.steps.push((source.join("\n"), expected.join("\n"), prompt_line));
+            continue;
+        }
+
+        let fenced = trimmed
+            .strip_prefix("```")
+            .is_some_and(|language| matches!(language.trim(), "python" | "py" | "python3"));
+        if fenced {
+            let margin = indentation(line);
+            let mut code = Vec::new();
+            index += 1;
+            while index < lines.len() && lines[index].trim() != "```" {
+                code.push(lines[index].get(margin..).unwrap_or("").trim_end().to_string());
+                index += 1;
+            }
+            examples.push(Found {
+                kind: ExampleKind::Code,
+                line: index - code.len(),
+                code,
+                steps: Vec::new(),
+            });
+            index += 1;
+            continue;
+        }
+
+        let directive = trimmed.starts_with(".. code-block:: python")
+            || trimmed.starts_with(".. code:: python")
+            || trimmed == ".. code-block:: py";
+        // Sections may hold prose, only their literal blocks are taken as code
+        if google && matches!(trimmed, "Example:" | "Examples:") {
+            section = Some(indentation(line));
+            index += 1;
+            continue;
+        }
+        let literal = trimmed.ends_with("::")
+            && !trimmed.starts_with("..")
+            && (section.is_some() || (google && matches!(trimmed, "Example::" | "Examples::")));
+        if directive || literal {
+            let margin = indentation(line);
+            let mut block = Vec::new();
+            let mut next = index + 1;
+            // Directive options such as `:linenos:` come before the code
+            while directive && next < lines.len() && lines[next].trim().starts_with(':') {
+                next += 1;
+            }
+            while next < lines.len() && lines[next].trim().is_empty() {
+                next += 1;
+            }
+            let first = next;
+            while next < lines.len()
+                && (lines[next].trim().is_empty() || indentation(lines[next]) > margin)
+            {
+                block.push(lines[next]);
+                next += 1;
+            }
+            while block.last().is_some_and(|l| l.trim().is_empty()) {
+                block.pop();
+            }
+            let is_doctest = block.iter().any(|l| l.trim_start().starts_with(">>>"));
+            if !block.is_empty() && !is_doctest {
+                let inner = block
+                    .iter()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(|l| indentation(l))
+                    .min()
+                    .unwrap_or(0);
+                examples.push(Found {
+                    kind: ExampleKind::Code,
+                    line: first,
+                    code: block
+                        .iter()
+                        .map(|l| l.get(inner..).unwrap_or("").trim_end().to_string())
+                        .collect(),
+                    steps: Vec::new(),
+                });
+                index = next;
+                continue;
+            }
+        }
+        index += 1;
+    }
+
+    if let Some(doctest) = doctest {
+        let position = examples.iter().position(|e| e.line > doctest.line).unwrap_or(examples.len());
+        examples.insert(position, doctest);
+    }
+    examples
+}
+
+/// Helpers of the generated pytest files. Doctest statements are compiled in
+/// `single` mode so that expression values are printed the way the
+/// interactive interpreter prints them.
+const PYTEST_HEADER: &str = r#"import contextlib
+import importlib
+import io
+
+
+def _namespace(module):
+    return dict(vars(importlib.import_module(module)))
+
+
+def _check(source, expected, namespace):
+    output = io.StringIO()
+    if expected.startswith("Traceback (most recent call last):"):
+        error = expected.strip().splitlines()[-1].split(":")[0].rsplit(".", 1)[-1]
+        try:
+            with contextlib.redirect_stdout(output):
+                exec(compile(source, "<doctest>", "single"), namespace)
+        except Exception as exc:
+            assert type(exc).__name__ == error
+            return
+        raise AssertionError(f"expected {error} from {source!r}")
+    with contextlib.redirect_stdout(output):
+        exec(compile(source, "<doctest>", "single"), namespace)
+    assert output.getvalue().rstrip() == expected.rstrip()
+"#;
+
+/// Writes the examples as a pytest module with one test function per
+/// example, named after the documented symbol and the example line.
+pub fn pytest_file(examples: &[Example]) -> String {
+    let mut text = String::from(PYTEST_HEADER);
+    for example in examples {
+        let name = example
+            .qualname
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        text.push_str(&format!("\n\ndef test_{}_line_{}():\n", name, example.line + 1));
+        text.push_str(&format!("    namespace = _namespace({})\n", python_string(&example.module)));
+        match example.kind {
+            ExampleKind::Doctest => {
+                for step in example.steps.iter() {
+                    text.push_str(&format!(
+                        "    _check({}, {}, namespace)\n",
+                        python_string(&step.source),
+                        python_string(&step.expected)
+                    ));
+                }
+            }
+            ExampleKind::Code => text.push_str(&format!(
+                "    exec(compile({}, \"<example>\", \"exec\"), namespace)\n",
+                python_string(&example.code)
+            )),
+        }
+    }
+    text
+}
+
+/// Writes the pytest module of the examples to a file.
+pub fn write_pytest(examples: &[Example], path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, pytest_file(examples))
+}
+
+/// Quotes a text as a python string literal.
+fn python_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            '\r' => literal.push_str("\\r"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
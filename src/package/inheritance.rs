@@ -0,0 +1,231 @@
+//! Class hierarchies across the modules of a package.
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+
+use super::Package;
+
+/// Member a class gets from one of its bases.
+#[derive(Debug, Clone)]
+pub struct InheritedMember {
+    pub name: String,
+
+    /// Qualified name of the first class in the MRO, after the class itself,
+    /// defining the member
+    pub defined_in: String,
+    pub node: Rc<RefCell<ContextNode>>,
+
+    /// Whether the class itself defines the member again
+    pub overridden: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InheritanceError {
+    /// The qualified name does not point to a class of the package
+    UnknownClass(String),
+
+    /// The class inherits from itself through the given class
+    Cycle(String),
+
+    /// No consistent C3 linearization exists for the class
+    InconsistentMro(String),
+}
+
+impl fmt::Display for InheritanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownClass(name) => write!(f, "unknown class `{}`", name),
+            Self::Cycle(name) => write!(f, "inheritance cycle through `{}`", name),
+            Self::InconsistentMro(name) => write!(f, "cannot create a consistent MRO for `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for InheritanceError {}
+
+impl Package {
+    /// Returns the bases of a class. Bases defined in the package are given
+    /// by their qualified name, other bases as written in the source without
+    /// subscripts. `object` is omitted.
+    pub fn bases(&self, qualified: &str) -> Result<Vec<String>, InheritanceError> {
+        let (module, _) = self
+            .split_qualified(qualified)
+            .ok_or_else(|| InheritanceError::UnknownClass(qualified.to_string()))?;
+        let node = self
+            .class(qualified)
+            .ok_or_else(|| InheritanceError::UnknownClass(qualified.to_string()))?;
+        let header = node.borrow().class_header.clone().unwrap_or_default();
+        let line = node.borrow().span.start_line.unwrap_or(0);
+
+        Ok(header
+            .bases
+            .iter()
+            .filter(|base| !base.starts_with('*'))
+            .map(|base| base.split('[').next().unwrap_or(base).trim().to_string())
+            .filter(|base| base != "object")
+            .map(|base| {
+                self.resolve_base(&module.name, &base, line)
+                    .filter(|resolved| resolved != qualified)
+                    .unwrap_or(base)
+            })
+            .collect())
+    }
+
+    /// Resolves a base class through the last module level binding of its
+    /// first name before the class statement, so that `class Foo(Foo)`
+    /// refers to an imported or earlier `Foo` rather than to itself.
+    fn resolve_base(&self, module: &str, name: &str, line: usize) -> Option<String> {
+        let module = self.modules.get(module)?;
+        let (first, rest) = match name.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (name, None),
+        };
+
+        let mut binding: Option<(usize, String)> = None;
+        for child in module.root.borrow().children.iter() {
+            let child = child.borrow();
+            let start = match child.span.start_line {
+                Some(start) if start < line => start,
+                _ => continue,
+            };
+            let target = match (&child.context_type, &child.import) {
+                (ContextType::CLASS, _) if child.identifier() == first => format!("{}.{}", module.name, first),
+                (ContextType::IMPORT, Some(import)) => {
+                    let imported = import.names.iter().find(|n| n.bound_name() == first);
+                    match (imported, module.aliases.get(first)) {
+                        (Some(_), Some(target)) => target.clone(),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            if binding.as_ref().is_none_or(|(previous, _)| start >= *previous) {
+                binding = Some((start, target));
+            }
+        }
+
+        let target = match binding {
+            Some((_, target)) => target,
+            // Names bound in other ways, e.g. in class bodies
+            None => return self.resolve_class(&module.name, name),
+        };
+        let target = match rest {
+            Some(rest) => format!("{}.{}", target, rest),
+            None => target,
+        };
+        if self.class(&target).is_some() {
+            return Some(target);
+        }
+        let (target_module, path) = self.split_qualified(&target)?;
+        if path.is_empty() || target_module.name == module.name {
+            return None;
+        }
+        self.resolve_class(&target_module.name, path)
+    }
+
+    /// Computes the C3 method resolution order of a class, starting with the
+    /// class itself.
+    pub fn mro(&self, qualified: &str) -> Result<Vec<String>, InheritanceError> {
+        if self.class(qualified).is_none() {
+            return Err(InheritanceError::UnknownClass(qualified.to_string()));
+        }
+        self.mro_with_stack(qualified, &mut Vec::new())
+    }
+
+    fn mro_with_stack(
+        &self,
+        qualified: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<Vec<String>, InheritanceError> {
+        // Bases outside of the package are leaves of the hierarchy
+        if self.class(qualified).is_none() {
+            return Ok(vec![qualified.to_string()]);
+        }
+        if stack.iter().any(|c| c == qualified) {
+            return Err(InheritanceError::Cycle(qualified.to_string()));
+        }
+
+        stack.push(qualified.to_string());
+        let bases = self.bases(qualified)?;
+        let mut sequences = Vec::with_capacity(bases.len() + 1);
+        for base in &bases {
+            sequences.push(self.mro_with_stack(base, stack)?);
+        }
+        sequences.push(bases);
+        stack.pop();
+
+        let mut mro = vec![qualified.to_string()];
+        loop {
+            sequences.retain(|s| !s.is_empty());
+            if sequences.is_empty() {
+                return Ok(mro);
+            }
+            let head = sequences
+                .iter()
+                .map(|s| &s[0])
+                .find(|candidate| !sequences.iter().any(|s| s[1..].contains(candidate)))
+                .cloned()
+                .ok_or_else(|| InheritanceError::InconsistentMro(qualified.to_string()))?;
+            for sequence in sequences.iter_mut() {
+                if sequence[0] == head {
+                    sequence.remove(0);
+                }
+            }
+            mro.push(head);
+        }
+    }
+
+    /// Lists the members a class gets from its bases, following the MRO.
+    ///
+    /// Each member appears once, provided by the first base defining it.
+    /// Members the class defines itself are reported as overridden.
+    pub fn inherited_members(&self, qualified: &str) -> Result<Vec<InheritedMember>, InheritanceError> {
+        let mro = self.mro(qualified)?;
+        let own = members(&self.class(qualified).unwrap())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<String>>();
+
+        let mut inherited: Vec<InheritedMember> = Vec::new();
+        for class_name in mro.iter().skip(1) {
+            let class_node = match self.class(class_name) {
+                Some(node) => node,
+                None => continue,
+            };
+            for (name, node) in members(&class_node) {
+                if inherited.iter().any(|m| m.name == name) {
+                    continue;
+                }
+                inherited.push(InheritedMember {
+                    overridden: own.contains(&name),
+                    name,
+                    defined_in: class_name.clone(),
+                    node,
+                });
+            }
+        }
+
+        Ok(inherited)
+    }
+}
+
+/// Named members defined directly in a class body.
+fn members(class_node: &Rc<RefCell<ContextNode>>) -> Vec<(String, Rc<RefCell<ContextNode>>)> {
+    class_node
+        .borrow()
+        .children
+        .iter()
+        .filter(|c| {
+            matches!(
+                c.borrow().context_type,
+                ContextType::METHOD
+                    | ContextType::CLASS
+                    | ContextType::ATTRIBUTE
+                    | ContextType::PROPERTY
+            )
+        })
+        .map(|c| (c.borrow().identifier().to_string(), Rc::clone(c)))
+        .collect()
+}
@@ -0,0 +1,222 @@
+//! Sphinx `objects.inv` inventories, written for the symbols of a package and
+//! read from other projects to link to their documentation.
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use regex::Regex;
+
+use crate::context_engine::definitions::ContextType;
+
+use super::{is_public_name, Package};
+
+const HEADER: &str = "# Sphinx inventory version 2";
+
+/// Entry line of the inventory, the same expression Sphinx reads them with
+const ITEM_MATCH: &str = r"^(?P<name>.+?)\s+(?P<type>\S+)\s+(?P<priority>-?\d+)\s+?(?P<uri>\S*)\s+(?P<display>.*)$";
+
+/// Documented object of an inventory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InventoryItem {
+    pub name: String,
+
+    /// Domain and role, e.g. `py:class`
+    pub role: String,
+    pub priority: i32,
+
+    /// Location relative to the documentation root, where `$` stands for
+    /// the name
+    pub uri: String,
+
+    /// Title shown instead of the name, if any
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum InventoryError {
+    Io(io::Error),
+
+    /// The file is not a version 2 inventory
+    Format(String),
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "cannot read inventory: {}", error),
+            Self::Format(message) => write!(f, "invalid inventory: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+impl From<io::Error> for InventoryError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A Sphinx version 2 inventory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+    pub project: String,
+    pub version: String,
+
+    /// Root of the documentation the URIs are relative to, set when the
+    /// inventory of another project is used to resolve references
+    pub base_url: Option<String>,
+    pub items: Vec<InventoryItem>,
+}
+
+impl Inventory {
+    /// Lists the public modules and symbols of a package. `page` is the
+    /// template of the page documenting a module, e.g. `api/{module}.html`.
+    pub fn from_package(package: &Package, project: &str, version: &str, page: &str) -> Self {
+        let table = package.symbol_table();
+        let mut items = Vec::new();
+        for (name, context_type) in table.symbols.iter() {
+            if !is_public_name(name) {
+                continue;
+            }
+            let (module, path) = match package.split_qualified(name) {
+                Some((module, path)) => (module.name.as_str(), path),
+                None => continue,
+            };
+            let uri = page.replace("{module}", module);
+            let parent_is_class = name
+                .rsplit_once('.')
+                .is_some_and(|(parent, _)| table.symbols.get(parent) == Some(&ContextType::CLASS));
+            let (role, uri) = match context_type {
+                ContextType::ROOT => ("py:module", format!("{}#module-$", uri)),
+                ContextType::CLASS => ("py:class", format!("{}#$", uri)),
+                ContextType::METHOD if parent_is_class => ("py:method", format!("{}#$", uri)),
+                ContextType::METHOD => ("py:function", format!("{}#$", uri)),
+                ContextType::PROPERTY => ("py:property", format!("{}#$", uri)),
+                ContextType::ATTRIBUTE => ("py:attribute", format!("{}#$", uri)),
+                _ if path.contains('.') => ("py:attribute", format!("{}#$", uri)),
+                _ => ("py:data", format!("{}#$", uri)),
+            };
+            items.push(InventoryItem {
+                name: name.clone(),
+                role: role.to_string(),
+                priority: if *context_type == ContextType::ROOT { 0 } else { 1 },
+                uri,
+                display_name: None,
+            });
+        }
+        Self {
+            project: project.to_string(),
+            version: version.to_string(),
+            base_url: None,
+            items,
+        }
+    }
+
+    /// Sets the root URL of the documentation of the inventory.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Returns the entry of a name, preferring python objects.
+    pub fn find(&self, name: &str) -> Option<&InventoryItem> {
+        let mut items = self.items.iter().filter(|i| i.name == name);
+        let first = items.next()?;
+        Some(
+            std::iter::once(first)
+                .chain(items)
+                .find(|i| i.role.starts_with("py:"))
+                .unwrap_or(first),
+        )
+    }
+
+    /// Returns the URL of an entry, absolute when the base URL is known.
+    pub fn url(&self, item: &InventoryItem) -> String {
+        let uri = match item.uri.strip_suffix('$') {
+            Some(prefix) => format!("{}{}", prefix, item.name),
+            None => item.uri.clone(),
+        };
+        match &self.base_url {
+            Some(base_url) => format!("{}/{}", base_url.trim_end_matches('/'), uri),
+            None => uri,
+        }
+    }
+
+    /// Encodes the inventory: a plain text header followed by the entries
+    /// compressed with zlib.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let mut bytes = format!(
+            "{}\n# Project: {}\n# Version: {}\n# The remainder of this file is compressed using zlib.\n",
+            HEADER, self.project, self.version
+        )
+        .into_bytes();
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for item in self.items.iter() {
+            let display_name = item.display_name.as_deref().unwrap_or("-");
+            writeln!(
+                encoder,
+                "{} {} {} {} {}",
+                item.name, item.role, item.priority, item.uri, display_name
+            )?;
+        }
+        bytes.extend(encoder.finish()?);
+        Ok(bytes)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes()?)
+    }
+
+    /// Decodes a version 2 inventory.
+    pub fn parse(bytes: &[u8]) -> Result<Self, InventoryError> {
+        let mut inventory = Self::default();
+        let mut rest = bytes;
+        for index in 0..4 {
+            let end = rest
+                .iter()
+                .position(|b| *b == b'\n')
+                .ok_or_else(|| InventoryError::Format(String::from("truncated header")))?;
+            let line = String::from_utf8_lossy(&rest[..end]).trim_end().to_string();
+            rest = &rest[end + 1..];
+            match index {
+                0 if line != HEADER => {
+                    return Err(InventoryError::Format(format!("unsupported header `{}`", line)))
+                }
+                1 => inventory.project = line.strip_prefix("# Project: ").unwrap_or_default().to_string(),
+                2 => inventory.version = line.strip_prefix("# Version: ").unwrap_or_default().to_string(),
+                _ => (),
+            }
+        }
+
+        let mut text = String::new();
+        ZlibDecoder::new(rest)
+            .read_to_string(&mut text)
+            .map_err(|e| InventoryError::Format(format!("cannot decompress entries: {}", e)))?;
+        let item_match = Regex::new(ITEM_MATCH).unwrap();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let captures = item_match
+                .captures(line)
+                .ok_or_else(|| InventoryError::Format(format!("invalid entry `{}`", line)))?;
+            let display_name = &captures["display"];
+            inventory.items.push(InventoryItem {
+                name: captures["name"].to_string(),
+                role: captures["type"].to_string(),
+                priority: captures["priority"].parse().unwrap_or(1),
+                uri: captures["uri"].to_string(),
+                display_name: (display_name != "-").then(|| display_name.to_string()),
+            });
+        }
+        Ok(inventory)
+    }
+
+    /// Reads an inventory file.
+    pub fn read(path: &Path) -> Result<Self, InventoryError> {
+        Self::parse(&fs::read(path)?)
+    }
+}
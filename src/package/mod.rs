@@ -0,0 +1,1311 @@
+//! Groups the context trees of several modules so that names can be resolved
+//! across module boundaries.
+pub mod changelog;
+pub mod dependencies;
+pub mod deprecations;
+pub mod diff;
+pub mod examples;
+pub mod inventory;
+pub mod references;
+pub mod render;
+pub mod search;
+pub mod inheritance;
+pub mod stubgen;
+pub mod stubs;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::rc::Rc;
+use std::{fs, io};
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+use crate::context_engine::rules::{DocstringStyle, LogicContext};
+use crate::context_engine::syntax::{slot_names, ImportStatement};
+use crate::context_engine::ContextProcessor;
+
+pub use changelog::{ChangelogWriter, Fragment, Section};
+pub use dependencies::DependencyGraph;
+pub use deprecations::{DeprecatedSymbol, DeprecationReport};
+pub use diff::{ApiChange, ApiDiff, ChangeKind, VersionBump};
+pub use examples::{DoctestStep, Example, ExampleKind};
+pub use inventory::{Inventory, InventoryError, InventoryItem};
+pub use references::{CrossReferences, Reference, ReferenceDiagnostic, ReferenceStyle, SymbolTable};
+pub use render::{RenderError, Renderer, TemplateNode};
+pub use search::{SearchDocument, SearchHit, SearchIndex};
+pub use inheritance::{InheritanceError, InheritedMember};
+pub use stubgen::StubGenerator;
+pub use stubs::StubReport;
+
+/// A parsed python module.
+pub struct Module {
+    /// Dotted module name, e.g. `package.sub.module`
+    pub name: String,
+
+    /// Whether the module is the `__init__` of a package
+    pub is_package: bool,
+
+    /// Whether the module was read from a `.pyi` stub without implementation
+    pub is_stub: bool,
+    pub file_lines: Vec<String>,
+    pub root: Rc<RefCell<ContextNode>>,
+
+    /// Docstring convention given by the parsing rules
+    pub docstring_style: DocstringStyle,
+
+    /// Local names bound by imports, mapped to the dotted name they refer to
+    pub(crate) aliases: HashMap<String, String>,
+}
+
+impl Module {
+    /// Parses the source of a module.
+    pub fn parse(name: &str, source: &str) -> Self {
+        Self::parse_with_rules(name, source, LogicContext::new())
+    }
+
+    /// Parses the source of a module, keeping the nodes allowed by the rules.
+    pub fn parse_with_rules(name: &str, source: &str, rules: LogicContext) -> Self {
+        let file_lines = source
+            .split('\n')
+            .map(|s| s.to_string())
+            .collect::<Vec<String>>();
+        let docstring_style = rules.docstring_style;
+        let root = ContextProcessor::load(file_lines.clone())
+            .with_rules(rules)
+            .parse_module();
+        let mut module = Self {
+            name: name.to_string(),
+            is_package: false,
+            is_stub: false,
+            file_lines,
+            root,
+            docstring_style,
+            aliases: HashMap::new(),
+        };
+        module.aliases = module.find_aliases();
+        module
+    }
+
+    /// Marks the module as the `__init__` of a package.
+    pub fn as_package(mut self) -> Self {
+        self.is_package = true;
+        self.aliases = self.find_aliases();
+        self
+    }
+
+    /// Returns the class defined at the given dotted path inside the module,
+    /// e.g. `Outer.Inner`.
+    pub fn class(&self, qualname: &str) -> Option<Rc<RefCell<ContextNode>>> {
+        let mut node = Rc::clone(&self.root);
+        for part in qualname.split('.') {
+            let child = node
+                .borrow()
+                .children
+                .iter()
+                .find(|c| {
+                    let c = c.borrow();
+                    c.context_type == ContextType::CLASS && c.identifier() == part
+                })
+                .map(Rc::clone)?;
+            node = child;
+        }
+        Some(node)
+    }
+
+    /// Resolves a relative import such as `..base` against the module.
+    fn absolute_module(&self, module: &str) -> String {
+        let level = module.chars().take_while(|c| *c == '.').count();
+        if level == 0 {
+            return module.to_string();
+        }
+        let mut parts = self.name.split('.').collect::<Vec<&str>>();
+        let drop = if self.is_package { level - 1 } else { level };
+        parts.truncate(parts.len().saturating_sub(drop));
+        let rest = &module[level..];
+        if !rest.is_empty() {
+            parts.push(rest);
+        }
+        parts.join(".")
+    }
+
+    /// Returns the import statements found at the top level of the module,
+    /// including the ones nested in control flow blocks.
+    pub fn imports(&self) -> Vec<ImportStatement> {
+        self.root
+            .borrow()
+            .children
+            .iter()
+            .filter_map(|c| c.borrow().import.clone())
+            .collect()
+    }
+
+    /// Returns the names listed by `__all__`, if the module defines it.
+    pub fn exported_names(&self) -> Option<Vec<String>> {
+        let root = self.root.borrow();
+        let all = root
+            .children
+            .iter()
+            .find(|c| c.borrow().context_type == ContextType::ALL)?
+            .borrow()
+            .variable
+            .as_ref()
+            .and_then(|v| v.value.clone())?;
+        Some(slot_names(&all).into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Returns the absolute name of the module an import statement reads.
+    pub fn imported_module(&self, import: &ImportStatement) -> String {
+        let dots = ".".repeat(import.level);
+        self.absolute_module(&format!("{}{}", dots, import.module))
+    }
+
+    /// Maps the names bound by the top level imports to the dotted names they
+    /// refer to.
+    fn find_aliases(&self) -> HashMap<String, String> {
+        let mut aliases = HashMap::new();
+        for import in self.imports() {
+            let module = self.imported_module(&import);
+            for name in &import.names {
+                let target = match (import.is_from, &name.alias) {
+                    (true, _) if module.is_empty() => name.name.clone(),
+                    (true, _) => format!("{}.{}", module, name.name),
+                    (false, Some(_)) => name.name.clone(),
+                    (false, None) => name.bound_name().to_string(),
+                };
+                aliases.insert(name.bound_name().to_string(), target);
+            }
+        }
+        aliases
+    }
+}
+
+/// Collection of parsed modules, indexed by their dotted name.
+#[derive(Default)]
+pub struct Package {
+    pub modules: BTreeMap<String, Module>,
+
+    /// Differences between the modules and the stubs merged into them
+    pub stub_reports: Vec<StubReport>,
+}
+
+impl Package {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_module(&mut self, module: Module) {
+        self.modules.insert(module.name.clone(), module);
+    }
+
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.get(name)
+    }
+
+    /// Parses every `.py` file below a directory, merging the `.pyi` stubs
+    /// found next to them.
+    ///
+    /// Module names are relative to the directory: `a/b.py` becomes `a.b`
+    /// and `a/__init__.py` becomes `a`.
+    pub fn from_dir(path: &Path) -> io::Result<Self> {
+        let mut package = Self::new();
+        package.read_dir(path, &[])?;
+        Ok(package)
+    }
+
+    fn read_dir(&mut self, path: &Path, prefix: &[String]) -> io::Result<()> {
+        let mut entries = fs::read_dir(path)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for entry in entries {
+            let stem = match entry.file_stem().and_then(|s| s.to_str()) {
+                Some(stem) => stem.to_string(),
+                None => continue,
+            };
+            if entry.is_dir() {
+                let mut prefix = prefix.to_vec();
+                prefix.push(stem);
+                self.read_dir(&entry, &prefix)?;
+            } else if entry
+                .extension()
+                .is_some_and(|e| e == "py" || e == "pyi")
+            {
+                let mut parts = prefix.to_vec();
+                if stem != "__init__" {
+                    parts.push(stem);
+                }
+                self.add_file(&entry, &parts.join("."), LogicContext::new())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parses a source file as the given module, `.pyi` files as stubs
+    /// merged with their implementation and `__init__` files as packages.
+    pub fn add_file(&mut self, path: &Path, name: &str, rules: LogicContext) -> io::Result<()> {
+        let source = fs::read_to_string(path)?;
+        let mut module = Module::parse_with_rules(name, &source, rules);
+        module.is_stub = path.extension().is_some_and(|e| e == "pyi");
+        if path.file_stem().is_some_and(|s| s == "__init__") {
+            module = module.as_package();
+        }
+        match module.is_stub {
+            true => self.add_stub(module),
+            false => self.add_implementation(module),
+        }
+        Ok(())
+    }
+
+    /// Splits a dotted name into the longest module prefix known to the
+    /// package and the remaining path inside that module.
+    pub fn split_qualified<'a>(&self, qualified: &'a str) -> Option<(&Module, &'a str)> {
+        let mut split = qualified.len();
+        loop {
+            let (module, rest) = (&qualified[..split], qualified.get(split + 1..).unwrap_or(""));
+            if let Some(module) = self.modules.get(module) {
+                return Some((module, rest));
+            }
+            split = qualified[..split].rfind('.')?;
+        }
+    }
+
+    /// Resolves a dotted name, as written inside the given module, to the
+    /// qualified name (`module.Class`) of a class defined in the package.
+    pub fn resolve_class(&self, module: &str, name: &str) -> Option<String> {
+        self.resolve_class_depth(module, name, 0)
+    }
+
+    fn resolve_class_depth(&self, module: &str, name: &str, depth: usize) -> Option<String> {
+        // Guard against modules re-exporting each other
+        if depth > 16 {
+            return None;
+        }
+        let module = self.modules.get(module)?;
+        let name = name.split('[').next().unwrap_or(name).trim();
+        let (first, rest) = match name.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (name, None),
+        };
+
+        if module.class(name).is_some() {
+            return Some(format!("{}.{}", module.name, name));
+        }
+
+        let target = module.aliases.get(first)?;
+        let target = match rest {
+            Some(rest) => format!("{}.{}", target, rest),
+            None => target.clone(),
+        };
+        let (target_module, path) = self.split_qualified(&target)?;
+        if path.is_empty() {
+            return None;
+        }
+        if target_module.name == module.name {
+            return None;
+        }
+        self.resolve_class_depth(&target_module.name.clone(), path, depth + 1)
+    }
+
+    /// Returns the class node for a qualified name such as `module.Class`.
+    pub fn class(&self, qualified: &str) -> Option<Rc<RefCell<ContextNode>>> {
+        let (module, path) = self.split_qualified(qualified)?;
+        module.class(path)
+    }
+}
+
+/// Tells whether no part of a qualified name is private, dunder names
+/// such as `__init__` being public.
+fn is_public_name(qualified: &str) -> bool {
+    qualified
+        .split('.')
+        .all(|part| !part.starts_with('_') || (part.starts_with("__") && part.ends_with("__")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context_engine::syntax::DeprecationSource;
+
+    fn package() -> Package {
+        let mut package = Package::new();
+        package.add_module(Module::parse("pkg", "from .base import Base\n").as_package());
+        package.add_module(Module::parse(
+            "pkg.base",
+            "
+class Base:
+    def run(self):
+        pass
+
+    def stop(self):
+        pass
+
+class LogMixin:
+    def log(self):
+        pass
+
+    def run(self):
+        pass
+",
+        ));
+        package.add_module(Module::parse(
+            "pkg.service",
+            "
+from pkg import Base
+from .base import (
+    LogMixin as Log,
+)
+
+class Service(Log, Base):
+    def stop(self):
+        pass
+
+class Broken(Base, Service):
+    pass
+",
+        ));
+        package
+    }
+
+    #[test]
+    fn test_resolve_class_through_imports() {
+        let package = package();
+
+        assert_eq!(
+            package.resolve_class("pkg.service", "Base"),
+            Some("pkg.base.Base".to_string())
+        );
+        assert_eq!(
+            package.resolve_class("pkg.service", "Log"),
+            Some("pkg.base.LogMixin".to_string())
+        );
+        assert_eq!(package.resolve_class("pkg.service", "Unknown"), None);
+    }
+
+    #[test]
+    fn test_mro() {
+        let package = package();
+
+        assert_eq!(
+            package.mro("pkg.service.Service").unwrap(),
+            vec!["pkg.service.Service", "pkg.base.LogMixin", "pkg.base.Base"]
+        );
+        assert_eq!(
+            package.mro("pkg.service.Broken"),
+            Err(InheritanceError::InconsistentMro("pkg.service.Broken".to_string()))
+        );
+        assert_eq!(
+            package.mro("pkg.service.Missing"),
+            Err(InheritanceError::UnknownClass("pkg.service.Missing".to_string()))
+        );
+
+        let mut package = package;
+        package.add_module(Module::parse(
+            "pkg.extended",
+            "
+from pkg.base import Base
+
+class Base(Base):
+    pass
+
+class Child(Base):
+    pass
+",
+        ));
+        assert_eq!(
+            package.mro("pkg.extended.Base").unwrap(),
+            vec!["pkg.extended.Base", "pkg.base.Base"]
+        );
+        assert_eq!(
+            package.mro("pkg.extended.Child").unwrap(),
+            vec!["pkg.extended.Child", "pkg.extended.Base", "pkg.base.Base"]
+        );
+    }
+
+    #[test]
+    fn test_inherited_members() {
+        let package = package();
+
+        let members = package
+            .inherited_members("pkg.service.Service")
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.name, m.defined_in, m.overridden))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            members,
+            vec![
+                ("log".to_string(), "pkg.base.LogMixin".to_string(), false),
+                ("run".to_string(), "pkg.base.LogMixin".to_string(), false),
+                ("stop".to_string(), "pkg.base.Base".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_stub() {
+        let mut package = Package::new();
+        package.add_stub(Module::parse_stub(
+            "client",
+            "
+from typing import overload
+
+TIMEOUT: float
+
+class Client:
+    retries: int
+    def __init__(self, url: str) -> None: ...
+    @overload
+    def get(self, key: str) -> bytes: ...
+    @overload
+    def get(self, key: int) -> list[bytes]: ...
+    def stream(self) -> Iterator[bytes]:
+        \"\"\"Stream the response.\"\"\"
+        ...
+
+def connect(url: str, *, timeout: float = ...) -> Client: ...
+",
+        ));
+        package.add_implementation(Module::parse(
+            "client",
+            "
+TIMEOUT = 3.0
+
+class Client:
+    \"\"\"HTTP client.\"\"\"
+    retries = 3
+
+    def __init__(self, url):
+        self.url = url
+
+    def get(self, key):
+        \"\"\"Fetch a key.\"\"\"
+
+    def stream(self):
+        pass
+
+    def close(self):
+        pass
+
+    def _reset(self):
+        pass
+",
+        ));
+
+        let module = package.module("client").unwrap();
+        assert!(!module.is_stub);
+        assert_eq!(
+            package.stub_reports,
+            vec![StubReport {
+                module: "client".to_string(),
+                missing_in_stub: vec!["Client.close".to_string(), "Client.url".to_string()],
+                missing_in_implementation: vec!["connect".to_string()],
+            }]
+        );
+
+        let root = module.root.borrow();
+        let timeout = root.children[0].borrow();
+        assert_eq!(
+            timeout.variable.as_ref().unwrap().annotation.as_deref(),
+            Some("float")
+        );
+
+        let client = module.class("Client").unwrap();
+        let client = client.borrow();
+        assert_eq!(client.docstring().as_deref(), Some("HTTP client."));
+        let member = |name: &str| {
+            client
+                .children
+                .iter()
+                .find(|c| c.borrow().identifier() == name)
+                .map(Rc::clone)
+                .unwrap()
+        };
+        assert_eq!(
+            member("__init__").borrow().signature.as_ref().unwrap().render(),
+            "(self, url: str) -> None"
+        );
+        let get = member("get");
+        assert_eq!(get.borrow().overloads.len(), 2);
+        assert_eq!(get.borrow().docstring().as_deref(), Some("Fetch a key."));
+        assert_eq!(
+            member("stream").borrow().docstring().as_deref(),
+            Some("Stream the response.")
+        );
+
+        let connect = root.children.last().unwrap().borrow();
+        assert_eq!(connect.name, "def connect");
+    }
+
+    #[test]
+    fn test_generate_stub() {
+        let module = Module::parse(
+            "geometry",
+            "
+import math
+from typing import overload
+
+__all__ = [\"Shape\", \"Color\", \"scale\"]
+
+PI = 3.14
+_CACHE: dict[str, float] = {}
+_MASK = 0x1F
+_SCALE = 1_000.5e-3
+_LIMIT = inf
+Vector = Tuple[float, float]
+
+class Color(Enum):
+    RED = 1
+    GREEN = 2
+
+@dataclass(frozen=True)
+class Shape(Base, metaclass=Meta):
+    \"\"\"A shape.\"\"\"
+    name: str
+    sides: int = 3
+
+    def __init__(self, name, sides=3):
+        self.name = name
+        self._area = None
+
+    @property
+    def area(self) -> float:
+        return self._area
+
+    @area.setter
+    def area(self, value):
+        self._area = value
+
+    async def draw(self, *, color: Color = Color.RED) -> None:
+        pass
+
+@overload
+def scale(value: int) -> int: ...
+@overload
+def scale(value: float) -> float: ...
+def scale(value):
+    return value * 2
+
+def _helper():
+    pass
+
+@overload
+def load(path: str) -> dict: ...
+@overload
+def load(path: bytes) -> list: ...
+",
+        );
+
+        let stub = StubGenerator::new().strip_private(true).generate(&module);
+
+        assert_eq!(
+            stub,
+            "import math
+from typing import overload
+
+__all__ = [\"Shape\", \"Color\", \"scale\"]
+
+PI: float
+Vector = Tuple[float, float]
+
+class Color(Enum):
+    RED = 1
+    GREEN = 2
+
+@dataclass(frozen=True)
+class Shape(Base, metaclass=Meta):
+    name: str
+    sides: int = ...
+    def __init__(self, name, sides=...): ...
+    @property
+    def area(self) -> float: ...
+    @area.setter
+    def area(self, value: float) -> None: ...
+    async def draw(self, *, color: Color = ...) -> None: ...
+
+@overload
+def scale(value: int) -> int: ...
+@overload
+def scale(value: float) -> float: ...
+@overload
+def load(path: str) -> dict: ...
+@overload
+def load(path: bytes) -> list: ...
+"
+        );
+
+        let stub = StubGenerator::new().generate(&module);
+        assert!(stub.contains("_CACHE: dict[str, float]\n"));
+        assert!(stub.contains("_MASK: int\n_SCALE: float\n_LIMIT: Incomplete\n"));
+        assert!(stub.contains("    _area: Incomplete\n"));
+        assert!(stub.contains("from _typeshed import Incomplete\n"));
+        assert!(stub.contains("def _helper(): ...\n"));
+    }
+
+    #[test]
+    fn test_api_diff() {
+        let mut old = Package::new();
+        old.add_module(Module::parse(
+            "sdk",
+            "
+from ._http import Session
+
+__all__ = [\"Client\", \"connect\", \"Session\", \"VERSION\", \"Pool\"]
+
+VERSION: str = \"1.0\"
+
+class Client:
+    def get(self, path, timeout=10, retries=3):
+        pass
+
+    def delete(self, path):
+        pass
+
+    @property
+    def url(self) -> str:
+        return self._url
+
+    @url.setter
+    def url(self, value):
+        self._url = value
+
+def connect(host, port):
+    pass
+
+class Pool:
+    size = 4
+
+    def acquire(self):
+        pass
+",
+        ));
+        old.add_module(Module::parse("sdk.legacy", "def run():\n    pass\n"));
+        old.add_module(Module::parse("sdk.__internal", "def run():\n    pass\n"));
+
+        let mut new = Package::new();
+        new.add_module(Module::parse(
+            "sdk",
+            "
+__all__ = [\"Client\", \"connect\", \"VERSION\", \"Token\"]
+
+VERSION: str = \"2.0\"
+
+class Client:
+    def get(self, path, *, timeout=30, retries=3, verify=True):
+        pass
+
+    @property
+    def url(self) -> str:
+        return self._url
+
+    def close(self):
+        pass
+
+def connect(host, port, user):
+    pass
+
+class Token:
+    def refresh(self):
+        pass
+",
+        ));
+
+        let diff = ApiDiff::compare(&old, &new);
+        let changes = diff
+            .changes
+            .iter()
+            .map(|c| (c.breaking, c.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (false, "sdk.Client.close: function added".to_string()),
+                (true, "sdk.Client.delete: function removed".to_string()),
+                (true, "sdk.Client.get: parameter `timeout` became keyword-only".to_string()),
+                (
+                    false,
+                    "sdk.Client.get: default of parameter `timeout` changed from `10` to `30`"
+                        .to_string()
+                ),
+                (true, "sdk.Client.get: parameter `retries` became keyword-only".to_string()),
+                (false, "sdk.Client.get: optional parameter `verify` added".to_string()),
+                (true, "sdk.Client.url: setter removed".to_string()),
+                (true, "sdk.Pool: class removed".to_string()),
+                (false, "sdk.Token: class added".to_string()),
+                (true, "sdk.__all__: `Session` removed from `__all__`".to_string()),
+                (true, "sdk.connect: required parameter `user` added".to_string()),
+                (true, "sdk.legacy: module removed".to_string()),
+            ]
+        );
+        assert_eq!(diff.version_bump(), VersionBump::Major);
+    }
+
+    #[test]
+    fn test_changelog() {
+        let mut old = Package::new();
+        old.add_module(Module::parse(
+            "sdk",
+            "
+def fetch(url):
+    pass
+
+def upload(path):
+    pass
+",
+        ));
+        let mut new = Package::new();
+        new.add_module(Module::parse(
+            "sdk",
+            "
+def fetch(url, *, timeout=None):
+    pass
+
+@deprecated(\"use fetch\")
+def upload(path):
+    pass
+
+def stream(url):
+    pass
+",
+        ));
+        let diff = ApiDiff::compare(&old, &new);
+        let writer = ChangelogWriter::new("1.4.0")
+            .date("2026-10-18")
+            .doc_url("https://docs.example.com/api/{module}.html#{symbol}");
+
+        assert_eq!(diff.version_bump(), VersionBump::Minor);
+        assert_eq!(
+            writer.markdown(&diff),
+            "## 1.4.0 (2026-10-18)
+
+### New APIs
+
+- [`sdk.stream`](https://docs.example.com/api/sdk.html#sdk.stream): function added
+
+### Deprecations
+
+- [`sdk.upload`](https://docs.example.com/api/sdk.html#sdk.upload): function deprecated
+
+### Other changes
+
+- [`sdk.fetch`](https://docs.example.com/api/sdk.html#sdk.fetch): optional parameter `timeout` added
+"
+        );
+        let names = writer
+            .fragments(&diff)
+            .into_iter()
+            .map(|f| f.file_name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "+sdk.stream.feature.md",
+                "+sdk.upload.removal.md",
+                "+sdk.fetch.misc.md"
+            ]
+        );
+        let names = writer
+            .clone()
+            .fragment_type(Section::Deprecation, "deprecation")
+            .fragments(&diff)
+            .into_iter()
+            .map(|f| f.file_name)
+            .collect::<Vec<_>>();
+        assert_eq!(names[1], "+sdk.upload.deprecation.md");
+
+        let mut diff = diff;
+        diff.changes.push(ApiChange {
+            kind: ChangeKind::Changed,
+            module: String::from("sdk"),
+            symbol: String::from("sdk.upload"),
+            context_type: ContextType::METHOD,
+            breaking: true,
+            description: String::from("required parameter `size` added"),
+        });
+        let names = writer.fragments(&diff).into_iter().map(|f| f.file_name).collect::<Vec<_>>();
+        assert_eq!(names[0], "+sdk.upload.removal.md");
+        assert_eq!(names[2], "+sdk.upload.removal.1.md");
+    }
+
+    #[test]
+    fn test_deprecations() {
+        let mut package = Package::new();
+        package.add_module(Module::parse(
+            "sdk",
+            "
+import warnings
+
+@deprecated(\"Use fetch instead, removed in 3.0\")
+def get(url):
+    pass
+
+def upload(path):
+    \"\"\"Upload a file.
+
+    .. deprecated:: 1.2
+       Use `sdk.Client.upload` instead.
+    \"\"\"
+    import warnings
+    warnings.warn(
+        \"upload() will be removed in 2.5\",
+        DeprecationWarning,
+        stacklevel=2,
+    )
+
+class Client:
+    @deprecated(version=\"1.1\", reason=\"use close\")
+    def stop(self):
+        pass
+
+    def close(self):
+        warnings.warn(\"closing twice\", ResourceWarning)
+",
+        ));
+
+        let report = package.deprecations();
+        let symbols = report
+            .symbols
+            .iter()
+            .map(|s| s.symbol.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(symbols, vec!["sdk.upload", "sdk.get", "sdk.Client.stop"]);
+
+        let upload = &report.symbols[0].deprecation;
+        assert_eq!(upload.source, DeprecationSource::Warning);
+        assert_eq!(upload.version.as_deref(), Some("1.2"));
+        assert_eq!(upload.removal.as_deref(), Some("2.5"));
+        assert_eq!(upload.replacement.as_deref(), Some("sdk.Client.upload"));
+        assert_eq!(upload.badge(), "deprecated since 1.2");
+
+        assert_eq!(report.due_for_removal("2.9").len(), 1);
+        assert_eq!(
+            report.to_string(),
+            "sdk.upload: deprecated since 1.2, removal in 2.5, use `sdk.Client.upload`
+sdk.get: deprecated, removal in 3.0, use `fetch`
+sdk.Client.stop: deprecated since 1.1, use `close`
+"
+        );
+    }
+
+    #[test]
+    fn test_examples() {
+        let module = Module::parse(
+            "mathx",
+            "
+def double(x):
+    \"\"\"
+    Double a number.
+
+    >>> double(2)
+    4
+    >>> for i in range(2):
+    ...     print(double(i))
+    0
+    2
+
+    Example::
+        total = double(3)
+        assert total == 6
+
+    ```python
+    print(\"done\")
+    ```
+    \"\"\"
+    return x * 2
+
+def half(x):
+    \"\"\"
+    Examples:
+        Call half to split a number.
+
+        ```python
+        half(4)
+        ```
+
+        Or run it in a loop::
+
+            for i in range(3):
+                half(i)
+
+    Returns:
+        The half of x.
+    \"\"\"
+",
+        );
+
+        let examples = module.examples();
+        assert_eq!(examples.len(), 5);
+        assert_eq!(examples[0].qualname, "mathx.double");
+        assert_eq!(examples[0].kind, ExampleKind::Doctest);
+        assert_eq!(examples[0].line, 5);
+        assert_eq!(
+            examples[0].steps[1],
+            DoctestStep {
+                source: "for i in range(2):\n    print(double(i))".to_string(),
+                expected: "0\n2".to_string(),
+                line: 7,
+            }
+        );
+        assert_eq!(examples[1].kind, ExampleKind::Code);
+        assert_eq!(examples[1].line, 13);
+        assert_eq!(examples[1].code, "total = double(3)\nassert total == 6");
+        assert_eq!(examples[2].code, "print(\"done\")");
+        assert_eq!(examples[2].line, 17);
+        assert_eq!(examples[3].qualname, "mathx.half");
+        assert_eq!(examples[3].code, "half(4)");
+        assert_eq!(examples[4].code, "for i in range(3):\n    half(i)");
+        assert_eq!(examples[4].line, 33);
+
+        let source = "def half(x):\n    \"\"\"\n    Example::\n\n        half(4)\n    \"\"\"\n";
+        let rules = LogicContext::new().docstring_style(DocstringStyle::Sphinx);
+        assert!(Module::parse_with_rules("mathx", source, rules).examples().is_empty());
+        assert_eq!(Module::parse("mathx", source).examples()[0].code, "half(4)");
+
+        let pytest = examples::pytest_file(&examples);
+        assert!(pytest.contains(
+            "def test_mathx_double_line_6():
+    namespace = _namespace(\"mathx\")
+    _check(\"double(2)\", \"4\", namespace)
+    _check(\"for i in range(2):\\n    print(double(i))\", \"0\\n2\", namespace)
+"
+        ));
+        assert!(pytest.contains(
+            "    exec(compile(\"total = double(3)\\nassert total == 6\", \"<example>\", \"exec\"), namespace)\n"
+        ));
+    }
+
+    #[test]
+    fn test_cross_references() {
+        let mut package = package();
+        package.add_module(Module::parse(
+            "pkg.client",
+            "
+from pkg.service import Service as Svc
+
+class Client:
+    \"\"\"Wraps a :class:`~pkg.base.Base` through :class:`Svc`.
+
+    Use :meth:`connect` or `Client.close()`, see :class:`.Service` and
+    [the mixin][pkg.base.LogMixin.log]. Returns ``None`` or `True`.
+    Unknown: :func:`missing_function` and [Service][].
+    \"\"\"
+
+    def connect(self):
+        \"\"\"Counterpart of :meth:`close`.\"\"\"
+
+    def close(self):
+        pass
+",
+        ));
+
+        let references = package.cross_references();
+        let resolved = references
+            .references
+            .iter()
+            .map(|r| (r.name.as_str(), r.target.as_deref(), r.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            vec![
+                ("pkg.base.Base", Some("pkg.base.Base"), 4),
+                ("Svc", Some("pkg.service.Service"), 4),
+                ("connect", Some("pkg.client.Client.connect"), 6),
+                ("Service", Some("pkg.service.Service"), 6),
+                ("Client.close", Some("pkg.client.Client.close"), 6),
+                ("pkg.base.LogMixin.log", Some("pkg.base.LogMixin.log"), 7),
+                ("True", None, 7),
+                ("missing_function", None, 8),
+                ("Service", None, 8),
+                ("close", Some("pkg.client.Client.close"), 12),
+            ]
+        );
+        assert_eq!(
+            references.target("pkg.client.Client", "Svc"),
+            Some("pkg.service.Service")
+        );
+        let diagnostics = references
+            .diagnostics()
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "pkg.client:9: unresolved reference `missing_function` in the docstring of `pkg.client.Client`",
+                "pkg.client:9: unresolved reference `Service` in the docstring of `pkg.client.Client`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inventory() {
+        let package = package();
+        let inventory = Inventory::from_package(&package, "pkg", "1.0", "api/{module}.html");
+        let lines = inventory
+            .items
+            .iter()
+            .map(|i| format!("{} {} {} {}", i.name, i.role, i.priority, i.uri))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                "pkg py:module 0 api/pkg.html#module-$",
+                "pkg.base py:module 0 api/pkg.base.html#module-$",
+                "pkg.base.Base py:class 1 api/pkg.base.html#$",
+                "pkg.base.Base.run py:method 1 api/pkg.base.html#$",
+                "pkg.base.Base.stop py:method 1 api/pkg.base.html#$",
+                "pkg.base.LogMixin py:class 1 api/pkg.base.html#$",
+                "pkg.base.LogMixin.log py:method 1 api/pkg.base.html#$",
+                "pkg.base.LogMixin.run py:method 1 api/pkg.base.html#$",
+                "pkg.service py:module 0 api/pkg.service.html#module-$",
+                "pkg.service.Broken py:class 1 api/pkg.service.html#$",
+                "pkg.service.Service py:class 1 api/pkg.service.html#$",
+                "pkg.service.Service.stop py:method 1 api/pkg.service.html#$",
+            ]
+        );
+
+        let bytes = inventory.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"# Sphinx inventory version 2\n# Project: pkg\n# Version: 1.0\n"));
+        assert_eq!(Inventory::parse(&bytes).unwrap(), inventory);
+        assert!(Inventory::parse(b"# Sphinx inventory version 1\n").is_err());
+
+        let stdlib = Inventory {
+            project: String::from("Python"),
+            version: String::from("3.12"),
+            base_url: None,
+            items: vec![
+                InventoryItem {
+                    name: String::from("int"),
+                    role: String::from("py:class"),
+                    priority: 1,
+                    uri: String::from("library/functions.html#$"),
+                    display_name: None,
+                },
+                InventoryItem {
+                    name: String::from("collections.OrderedDict"),
+                    role: String::from("py:class"),
+                    priority: 1,
+                    uri: String::from("library/collections.html#$"),
+                    display_name: None,
+                },
+            ],
+        };
+        let stdlib = Inventory::parse(&stdlib.to_bytes().unwrap())
+            .unwrap()
+            .with_base_url("https://docs.python.org/3/");
+
+        let mut package = package;
+        package.add_module(Module::parse(
+            "pkg.cache",
+            "
+from collections import OrderedDict
+
+def evict(count):
+    \"\"\"Drops :class:`int` entries from an :class:`OrderedDict`, see :class:`Missing`.\"\"\"
+",
+        ));
+        let references = package.cross_references_with(&[stdlib]);
+        let resolved = references
+            .references
+            .iter()
+            .map(|r| (r.name.as_str(), r.target.as_deref(), r.url.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resolved,
+            vec![
+                ("int", Some("int"), Some("https://docs.python.org/3/library/functions.html#int")),
+                (
+                    "OrderedDict",
+                    Some("collections.OrderedDict"),
+                    Some("https://docs.python.org/3/library/collections.html#collections.OrderedDict")
+                ),
+                ("Missing", None, None),
+            ]
+        );
+        assert_eq!(references.diagnostics().len(), 1);
+    }
+
+    #[test]
+    fn test_search_index() {
+        let mut package = Package::new();
+        package.add_module(Module::parse(
+            "pkg.retry",
+            "
+\"\"\"Retrying of failed requests.\"\"\"
+
+class RetryPolicy:
+    \"\"\"Decides when failed requests are retried.
+
+    Delays grow exponentially between attempts.
+    \"\"\"
+
+    max_attempts = 3
+
+    def should_retry(self, error):
+        \"\"\"Tells whether the policy allows another attempt.\"\"\"
+
+    def _backoff(self):
+        \"\"\"Computes the delay of the policy.\"\"\"
+
+def fetch(url, policy=None):
+    \"\"\"Downloads a page.\"\"\"
+",
+        ));
+
+        assert_eq!(
+            search::tokenize("HTTPServer retries the_cachedValues"),
+            vec!["http", "server", "retry", "cach", "valu"]
+        );
+
+        let index = SearchIndex::from_package(&package);
+        let names = |query: &str| {
+            index
+                .search(query)
+                .iter()
+                .map(|h| h.document.qualname.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names("retry policy"),
+            vec![
+                "pkg.retry.RetryPolicy",
+                "pkg.retry.RetryPolicy.should_retry",
+                "pkg.retry.RetryPolicy.max_attempts",
+            ]
+        );
+        assert_eq!(names("backoff"), Vec::<String>::new());
+        assert_eq!(names("downloading"), vec!["pkg.retry.fetch"]);
+        assert_eq!(names("the"), Vec::<String>::new());
+        assert_eq!(
+            index.search("retry policy")[0].to_string(),
+            "pkg.retry.RetryPolicy (class): Decides when failed requests are retried."
+        );
+
+        let json = index.to_json().unwrap();
+        assert!(json.contains("\"kind\":\"method\""));
+        assert_eq!(SearchIndex::from_json(&json).unwrap(), index);
+    }
+
+    #[test]
+    fn test_render() {
+        let module = Module::parse(
+            "pkg.shapes",
+            "
+\"\"\"Geometric shapes.\"\"\"
+from typing import deprecated
+
+RADIUS: float = 1.0
+
+class Circle(Shape):
+    \"\"\"A round shape.\"\"\"
+
+    def area(self) -> float:
+        \"\"\"Computes the area.\"\"\"
+
+    @property
+    def diameter(self) -> float:
+        return 2
+
+    def _cache(self):
+        pass
+
+@deprecated(\"use Circle\")
+def circle(radius: float = 1.0) -> Circle:
+    pass
+",
+        );
+
+        let text = Renderer::new().render_module(&module).unwrap();
+        assert_eq!(
+            text,
+            "# `pkg.shapes`
+
+Geometric shapes.
+
+## `RADIUS: float = 1.0`
+
+## `class Circle(Shape)`
+
+A round shape.
+
+### `area(self) -> float`
+
+Computes the area.
+
+### `diameter: float` (property)
+
+## `circle(radius: float = 1.0) -> Circle`
+
+> **deprecated**
+"
+        );
+
+        let renderer = Renderer::new()
+            .template("layout.md", "{% block content %}{% endblock %}")
+            .template(
+                "function.md",
+                "{% extends \"member.md\" %}{% block heading %}def {{ node.name }}{% endblock %}{% block body %}{% endblock %}",
+            )
+            .template("method.md", "- {{ node.name }}\n")
+            .template("class.md", "{% for member in node.members %}{{ render(member) }}{% endfor %}")
+            .template("data.md", "")
+            .template("property.md", "");
+        assert_eq!(
+            renderer.render_module(&module).unwrap(),
+            "Geometric shapes.
+
+- area
+## def circle
+
+> **deprecated**
+"
+        );
+
+        let error = Renderer::new()
+            .template("class.md", "{{ render(node, 1) }}")
+            .render_module(&module)
+            .unwrap_err();
+        assert!(matches!(error, RenderError::Template(_)));
+        assert!(error.to_string().ends_with("(class.md:1)"), "{}", error);
+        assert!(Renderer::new().extension("html").render_module(&module).is_err());
+
+        let error = Renderer::new()
+            .template("class.md", "{{ render(node) }}")
+            .render_module(&module)
+            .unwrap_err();
+        assert!(error.to_string().contains("`render` nested more than 32 times"), "{}", error);
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let mut package = package();
+        package.add_module(Module::parse(
+            "pkg.base",
+            "
+import pkg.service
+
+if TYPE_CHECKING:
+    from pkg import helpers
+",
+        ));
+        package.add_module(Module::parse("pkg.helpers", "from . import base\n"));
+
+        let graph = package.dependency_graph(false);
+        assert_eq!(graph.cycles(), vec![vec!["pkg", "pkg.base", "pkg.service"]]);
+        assert!(graph.to_dot().contains("\"pkg.service\" -> \"pkg.base\" [color=red];"));
+        assert!(graph.to_dot().contains("\"pkg.helpers\" -> \"pkg.base\";"));
+
+        let graph = package.dependency_graph(true);
+        assert_eq!(
+            graph.cycles(),
+            vec![vec!["pkg", "pkg.base", "pkg.helpers", "pkg.service"]]
+        );
+
+        let mut graph = DependencyGraph::default();
+        graph
+            .edges
+            .insert("a".to_string(), ["b".to_string()].into_iter().collect());
+        assert!(graph.cycles().is_empty());
+    }
+}
@@ -0,0 +1,381 @@
+//! Cross-references between docstrings and the symbols of a package.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+use regex::Regex;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+
+use super::examples::leading_blank_lines;
+use super::inventory::Inventory;
+use super::{Module, Package};
+
+/// Sphinx roles: `:class:`Foo``, `:py:meth:`Foo.bar``, ...
+const ROLE_REFERENCE: &str = r":(?:py:)?(?P<role>class|meth|func|attr|mod|data|exc|obj|const|type):`(?P<target>[^`]+)`";
+
+/// Markdown references: `[Foo][]` and `[text][Foo]`
+const MARKDOWN_REFERENCE: &str = r"\[(?P<text>[^\]]+)\]\[(?P<target>[^\]]*)\]";
+
+/// Backticked spans, of which only single backticks around a dotted name
+/// such as `Foo` or `Foo.bar()` are references, not ``literals``
+const BACKTICK_REFERENCE: &str = r"(?P<open>`+)(?P<target>[^`]+)(?P<close>`+)";
+
+const DOTTED_NAME: &str = r"^[A-Za-z_][\w.]*(?:\(\))?$";
+
+/// Symbols of a package by qualified name, including the modules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    pub symbols: BTreeMap<String, ContextType>,
+}
+
+impl SymbolTable {
+    pub fn contains(&self, qualified: &str) -> bool {
+        self.symbols.contains_key(qualified)
+    }
+}
+
+/// How a docstring refers to a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReferenceStyle {
+    /// Sphinx role, e.g. `class` for `:class:`Foo``
+    Role(String),
+    Markdown,
+    Backticks,
+}
+
+/// Reference written in a docstring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Qualified name of the documented symbol holding the reference
+    pub source: String,
+
+    /// Module of the documented symbol
+    pub module: String,
+
+    /// Name as written, without title, `~` or `.` prefix
+    pub name: String,
+    pub style: ReferenceStyle,
+
+    /// Whether the name has a `.` prefix, which lets it match the end of any
+    /// qualified name
+    pub suffix: bool,
+
+    /// Zero based source line
+    pub line: usize,
+
+    /// Qualified name of the referenced symbol
+    pub target: Option<String>,
+
+    /// Link to the documentation of another project, for targets found in
+    /// one of its inventories
+    pub url: Option<String>,
+}
+
+/// Reference that could not be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceDiagnostic {
+    pub module: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReferenceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.module, self.line + 1, self.message)
+    }
+}
+
+/// References of every docstring of a package.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrossReferences {
+    pub references: Vec<Reference>,
+}
+
+impl CrossReferences {
+    /// Returns the target of a reference written in the docstring of a
+    /// symbol, for renderers turning references into links.
+    pub fn target(&self, source: &str, name: &str) -> Option<&str> {
+        self.references
+            .iter()
+            .find(|r| r.source == source && r.name == name)
+            .and_then(|r| r.target.as_deref())
+    }
+
+    /// Reports the unresolved references. Plain backticked names are often
+    /// code formatting rather than references, so they are left out.
+    pub fn diagnostics(&self) -> Vec<ReferenceDiagnostic> {
+        self.references
+            .iter()
+            .filter(|r| r.target.is_none() && r.style != ReferenceStyle::Backticks)
+            .map(|r| ReferenceDiagnostic {
+                module: r.module.clone(),
+                line: r.line,
+                message: format!("unresolved reference `{}` in the docstring of `{}`", r.name, r.source),
+            })
+            .collect()
+    }
+}
+
+impl Package {
+    /// Lists the modules and the symbols defined at their top level or in
+    /// class bodies.
+    pub fn symbol_table(&self) -> SymbolTable {
+        let mut table = SymbolTable::default();
+        for module in self.modules.values() {
+            table.symbols.insert(module.name.clone(), ContextType::ROOT);
+            collect_symbols(&module.root, &module.name, &mut table);
+        }
+        table
+    }
+
+    /// Finds the references of every docstring and resolves them against the
+    /// symbol table of the package.
+    pub fn cross_references(&self) -> CrossReferences {
+        self.cross_references_with(&[])
+    }
+
+    /// Like `cross_references`, resolving the names missing from the package
+    /// against the inventories of other projects, in order.
+    pub fn cross_references_with(&self, inventories: &[Inventory]) -> CrossReferences {
+        let table = self.symbol_table();
+        let patterns = ReferencePatterns::new();
+        let mut references = Vec::new();
+        for module in self.modules.values() {
+            collect_references(&module.root, module, &module.name, &patterns, &mut references);
+        }
+        for reference in references.iter_mut() {
+            reference.target = self.resolve_reference(&table, reference);
+            if reference.target.is_none() {
+                if let Some((target, url)) = self.resolve_external(inventories, reference) {
+                    reference.target = Some(target);
+                    reference.url = Some(url);
+                }
+            }
+        }
+        CrossReferences { references }
+    }
+
+    /// Looks a name up in external inventories, as written and as bound by
+    /// the imports of the module, e.g. `OrderedDict` imported from
+    /// `collections`.
+    fn resolve_external(&self, inventories: &[Inventory], reference: &Reference) -> Option<(String, String)> {
+        let name = reference.name.as_str();
+        let mut candidates = vec![name.to_string()];
+        if let Some(module) = self.modules.get(&reference.module) {
+            let (first, rest) = match name.split_once('.') {
+                Some((first, rest)) => (first, Some(rest)),
+                None => (name, None),
+            };
+            if let Some(target) = module.aliases.get(first) {
+                candidates.push(match rest {
+                    Some(rest) => format!("{}.{}", target, rest),
+                    None => target.clone(),
+                });
+            }
+        }
+        candidates.iter().find_map(|candidate| {
+            inventories.iter().find_map(|inventory| {
+                inventory
+                    .find(candidate)
+                    .map(|item| (item.name.clone(), inventory.url(item)))
+            })
+        })
+    }
+
+    /// Resolves a name the way Sphinx does: as a qualified name, then
+    /// relative to the documented symbol and its parents, then through the
+    /// imports of the module and finally, for names with a `.` prefix, as
+    /// the unique symbol ending with it.
+    pub fn resolve_reference(&self, table: &SymbolTable, reference: &Reference) -> Option<String> {
+        let name = reference.name.as_str();
+        if table.contains(name) {
+            return Some(name.to_string());
+        }
+
+        let mut scope = reference.source.as_str();
+        loop {
+            let candidate = format!("{}.{}", scope, name);
+            if table.contains(&candidate) {
+                return Some(candidate);
+            }
+            match scope.rfind('.') {
+                Some(index) if scope.len() > reference.module.len() => scope = &scope[..index],
+                _ => break,
+            }
+        }
+
+        if let Some(target) = self.resolve_import(table, &reference.module, name, 0) {
+            return Some(target);
+        }
+
+        if !reference.suffix {
+            return None;
+        }
+        let suffix = format!(".{}", name);
+        let mut matches = table.symbols.keys().filter(|s| s.ends_with(&suffix));
+        match (matches.next(), matches.next()) {
+            (Some(symbol), None) => Some(symbol.clone()),
+            _ => None,
+        }
+    }
+
+    /// Follows the import binding the first part of a name in a module.
+    fn resolve_import(&self, table: &SymbolTable, module: &str, name: &str, depth: usize) -> Option<String> {
+        // Guard against modules re-exporting each other
+        if depth > 16 {
+            return None;
+        }
+        let module = self.modules.get(module)?;
+        let (first, rest) = match name.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (name, None),
+        };
+        let target = module.aliases.get(first)?;
+        let target = match rest {
+            Some(rest) => format!("{}.{}", target, rest),
+            None => target.clone(),
+        };
+        if table.contains(&target) {
+            return Some(target);
+        }
+        let (target_module, path) = self.split_qualified(&target)?;
+        if path.is_empty() || target_module.name == module.name {
+            return None;
+        }
+        self.resolve_import(table, &target_module.name.clone(), path, depth + 1)
+    }
+}
+
+fn collect_symbols(node: &Rc<RefCell<ContextNode>>, prefix: &str, table: &mut SymbolTable) {
+    for child in node.borrow().children.iter() {
+        let child_node = child.borrow();
+        let documented = matches!(
+            child_node.context_type,
+            ContextType::CLASS
+                | ContextType::METHOD
+                | ContextType::PROPERTY
+                | ContextType::VARIABLE
+                | ContextType::ALIAS
+                | ContextType::ATTRIBUTE
+        );
+        if !documented {
+            continue;
+        }
+        let qualified = format!("{}.{}", prefix, child_node.identifier());
+        table
+            .symbols
+            .entry(qualified.clone())
+            .or_insert_with(|| child_node.context_type.clone());
+        if child_node.context_type == ContextType::CLASS {
+            collect_symbols(child, &qualified, table);
+        }
+    }
+}
+
+fn collect_references(
+    node: &Rc<RefCell<ContextNode>>,
+    module: &Module,
+    qualname: &str,
+    patterns: &ReferencePatterns,
+    references: &mut Vec<Reference>,
+) {
+    let node = node.borrow();
+    let docstring = node
+        .children
+        .iter()
+        .find(|c| c.borrow().context_type == ContextType::DOCSTRING);
+    if let (Some(docstring), Some(text)) = (docstring, node.docstring()) {
+        let docstring = docstring.borrow();
+        let start = docstring.span.start_line.unwrap_or(0)
+            + docstring.value.as_deref().map(leading_blank_lines).unwrap_or(0);
+        for (line, name, style) in patterns.find(&text) {
+            let suffix = name.starts_with('.');
+            references.push(Reference {
+                source: qualname.to_string(),
+                module: module.name.clone(),
+                name: name.trim_start_matches('.').to_string(),
+                style,
+                suffix,
+                line: start + line,
+                target: None,
+                url: None,
+            });
+        }
+    }
+
+    for child in node.children.iter() {
+        if child.borrow().context_type == ContextType::DOCSTRING {
+            continue;
+        }
+        let name = format!("{}.{}", qualname, child.borrow().identifier());
+        collect_references(child, module, &name, patterns, references);
+    }
+}
+
+/// Compiled reference patterns, shared by the docstrings of a package.
+struct ReferencePatterns {
+    role: Regex,
+    markdown: Regex,
+    backticks: Regex,
+    dotted_name: Regex,
+}
+
+impl ReferencePatterns {
+    fn new() -> Self {
+        Self {
+            role: Regex::new(ROLE_REFERENCE).unwrap(),
+            markdown: Regex::new(MARKDOWN_REFERENCE).unwrap(),
+            backticks: Regex::new(BACKTICK_REFERENCE).unwrap(),
+            dotted_name: Regex::new(DOTTED_NAME).unwrap(),
+        }
+    }
+
+    /// Finds the references of a cleaned docstring with the index of their
+    /// line. Role targets keep their `.` prefix.
+    fn find(&self, docstring: &str) -> Vec<(usize, String, ReferenceStyle)> {
+        let mut references = Vec::new();
+        for (index, line) in docstring.lines().enumerate() {
+            let mut taken: Vec<(usize, usize)> = Vec::new();
+            for captures in self.role.captures_iter(line) {
+                let whole = captures.get(0).unwrap();
+                taken.push((whole.start(), whole.end()));
+                let target = &captures["target"];
+                // `title <target>` gives an explicit title, `!name` disables the link
+                let target = match (target.rfind('<'), target.strip_suffix('>')) {
+                    (Some(open), Some(_)) => &target[open + 1..target.len() - 1],
+                    _ => target,
+                };
+                if target.starts_with('!') {
+                    continue;
+                }
+                let target = target.trim_start_matches('~').trim_end_matches("()");
+                references.push((index, target.to_string(), ReferenceStyle::Role(captures["role"].to_string())));
+            }
+            for captures in self.markdown.captures_iter(line) {
+                let whole = captures.get(0).unwrap();
+                taken.push((whole.start(), whole.end()));
+                let target = match &captures["target"] {
+                    "" => &captures["text"],
+                    target => target,
+                };
+                let target = target.trim_matches('`').trim_end_matches("()");
+                references.push((index, target.to_string(), ReferenceStyle::Markdown));
+            }
+            for captures in self.backticks.captures_iter(line) {
+                let target = captures.name("target").unwrap();
+                let single = captures["open"].len() == 1 && captures["close"].len() == 1;
+                if !single
+                    || !self.dotted_name.is_match(target.as_str())
+                    || taken.iter().any(|(start, end)| target.start() >= *start && target.end() <= *end)
+                {
+                    continue;
+                }
+                let name = target.as_str().trim_end_matches("()");
+                references.push((index, name.to_string(), ReferenceStyle::Backticks));
+            }
+        }
+        references
+    }
+}
@@ -0,0 +1,385 @@
+//! Rendering of module trees through templates, with built-in Markdown
+//! templates that users can override or extend.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{fs, io};
+
+use minijinja::value::Value;
+use minijinja::{Environment, Error, ErrorKind, State};
+use serde::Serialize;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+
+use super::search::summary;
+use super::{Module, Package};
+
+/// Nesting of `render` calls past which a template is taken as rendering
+/// itself forever
+const MAX_RENDER_DEPTH: usize = 32;
+
+/// Built-in templates. Member kinds extend `member.md`, which defines the
+/// `heading` and `body` blocks, and modules extend `layout.md`. Members are
+/// rendered with the template of their kind by the `render` function.
+const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "layout.md",
+        "{% block title %}{{ '#' * node.level }} `{{ node.qualname }}`{% endblock +%}\n\n{% block content %}{% endblock %}",
+    ),
+    (
+        "module.md",
+        r#"{% extends "layout.md" %}
+{% block content %}
+{% if node.docstring %}
+{{ node.docstring }}
+
+{% endif %}
+{% for member in node.members %}{{ render(member) }}{% endfor %}
+{% endblock %}"#,
+    ),
+    (
+        "member.md",
+        r#"{{ '#' * node.level }} {% block heading %}`{{ node.name }}`{% endblock +%}
+
+{% if node.deprecated %}
+> **{{ node.deprecated }}**
+
+{% endif %}
+{% block body %}
+{% if node.docstring %}
+{{ node.docstring }}
+
+{% endif %}
+{% endblock %}"#,
+    ),
+    (
+        "class.md",
+        r#"{% extends "member.md" %}
+{% block heading %}`class {{ node.name }}{% if node.bases %}({{ node.bases | join(", ") }}){% endif %}`{% endblock %}
+{% block body %}
+{{ super() }}{% for member in node.members %}{{ render(member) }}{% endfor %}
+{% endblock %}"#,
+    ),
+    (
+        "function.md",
+        r#"{% extends "member.md" %}
+{% block heading %}`{{ node.name }}{{ node.signature }}`{% endblock %}"#,
+    ),
+    (
+        "method.md",
+        r#"{% extends "function.md" %}"#,
+    ),
+    (
+        "property.md",
+        r#"{% extends "member.md" %}
+{% block heading %}`{{ node.name }}{% if node.annotation %}: {{ node.annotation }}{% endif %}` (property){% endblock %}"#,
+    ),
+    (
+        "attribute.md",
+        r#"{% extends "member.md" %}
+{% block heading %}`{{ node.name }}{% if node.annotation %}: {{ node.annotation }}{% endif %}{% if node.value %} = {{ node.value }}{% endif %}`{% endblock %}"#,
+    ),
+    (
+        "data.md",
+        r#"{% extends "attribute.md" %}"#,
+    ),
+];
+
+/// Node of the tree handed to the templates as `node`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TemplateNode {
+    pub name: String,
+
+    /// Qualified name, e.g. `pkg.module.Class.method`
+    pub qualname: String,
+
+    /// Name of the template rendering the node: `module`, `class`,
+    /// `function`, `method`, `property`, `attribute` or `data`
+    pub kind: String,
+
+    /// Heading level, 1 for modules
+    pub level: usize,
+
+    /// Rendered parameters and return annotation of functions
+    pub signature: Option<String>,
+
+    /// Type of variables, attributes and properties
+    pub annotation: Option<String>,
+    pub value: Option<String>,
+    pub bases: Vec<String>,
+    pub decorators: Vec<String>,
+
+    /// Deprecation badge, e.g. `deprecated since 2.0`
+    pub deprecated: Option<String>,
+    pub docstring: Option<String>,
+
+    /// First paragraph of the docstring
+    pub summary: Option<String>,
+
+    /// Zero based line of the definition
+    pub line: Option<usize>,
+    pub members: Vec<TemplateNode>,
+}
+
+impl TemplateNode {
+    /// Builds the template tree of a module.
+    pub fn from_module(module: &Module, include_private: bool) -> Self {
+        let root = module.root.borrow();
+        let docstring = root.docstring();
+        TemplateNode {
+            name: module.name.rsplit('.').next().unwrap_or(&module.name).to_string(),
+            qualname: module.name.clone(),
+            kind: String::from("module"),
+            level: 1,
+            signature: None,
+            annotation: None,
+            value: None,
+            bases: Vec::new(),
+            decorators: Vec::new(),
+            deprecated: root.deprecation.as_ref().map(|d| d.badge()),
+            summary: docstring.as_deref().map(summary),
+            docstring,
+            line: None,
+            members: members(&module.root, &module.name, 2, include_private),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Io(io::Error),
+
+    /// Syntax or evaluation error of a template, with its name and line
+    Template(minijinja::Error),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "cannot write the documentation: {}", error),
+            Self::Template(error) => {
+                write!(f, "{}", error)?;
+                match (error.name(), error.line()) {
+                    (Some(name), Some(line)) => write!(f, " ({}:{})", name, line),
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<io::Error> for RenderError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<minijinja::Error> for RenderError {
+    fn from(error: minijinja::Error) -> Self {
+        Self::Template(error)
+    }
+}
+
+/// Renders modules with one template per node kind, looked up as
+/// `<kind>.<extension>`. User templates replace the built-in templates of
+/// the same name and may extend or include any other template.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    templates: BTreeMap<String, String>,
+    extension: String,
+    include_private: bool,
+
+    /// Private member policy of single modules, overriding `include_private`
+    private_modules: BTreeMap<String, bool>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self {
+            templates: DEFAULT_TEMPLATES
+                .iter()
+                .map(|(name, source)| (name.to_string(), source.to_string()))
+                .collect(),
+            extension: String::from("md"),
+            include_private: false,
+            private_modules: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the extension of the kind templates and of the written files.
+    /// Templates of `.html` files have their values escaped.
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.extension = extension.trim_start_matches('.').to_string();
+        self
+    }
+
+    /// Adds a template, replacing the template of the same name.
+    pub fn template(mut self, name: &str, source: &str) -> Self {
+        self.templates.insert(name.to_string(), source.to_string());
+        self
+    }
+
+    /// Adds the templates of a directory, named after their path relative
+    /// to it.
+    pub fn template_dir(mut self, path: &Path) -> io::Result<Self> {
+        let mut pending = vec![path.to_path_buf()];
+        while let Some(directory) = pending.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let entry_path = entry?.path();
+                if entry_path.is_dir() {
+                    pending.push(entry_path);
+                    continue;
+                }
+                let name = entry_path
+                    .strip_prefix(path)
+                    .unwrap_or(&entry_path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                self.templates.insert(name, fs::read_to_string(&entry_path)?);
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn include_private(mut self, include_private: bool) -> Self {
+        self.include_private = include_private;
+        self
+    }
+
+    /// Sets whether the private members of one module are rendered.
+    pub fn include_private_in(mut self, module: &str, include_private: bool) -> Self {
+        self.private_modules.insert(module.to_string(), include_private);
+        self
+    }
+
+    fn environment(&self) -> Result<Environment<'_>, RenderError> {
+        let mut environment = Environment::new();
+        environment.set_trim_blocks(true);
+        environment.set_lstrip_blocks(true);
+        environment.set_keep_trailing_newline(true);
+        for (name, source) in self.templates.iter() {
+            environment.add_template(name, source)?;
+        }
+        let extension = self.extension.clone();
+        // Each member is rendered on its own, so that the template of a kind
+        // may extend the same template as the one including it. The nesting
+        // is counted in `depth`, which the recursion limit of minijinja does
+        // not see across renders.
+        environment.add_function("render", move |state: &State, node: Value| {
+            let depth = state.lookup("depth").and_then(|d| usize::try_from(d).ok()).unwrap_or(0);
+            if depth >= MAX_RENDER_DEPTH {
+                return Err(Error::new(
+                    ErrorKind::InvalidOperation,
+                    format!("`render` nested more than {} times", MAX_RENDER_DEPTH),
+                ));
+            }
+            let kind = node.get_attr("kind")?;
+            let template = state.env().get_template(&format!("{}.{}", kind, extension))?;
+            let text = template.render(minijinja::context! { node => node, depth => depth + 1 })?;
+            Ok(Value::from_safe_string(text))
+        });
+        Ok(environment)
+    }
+
+    pub fn render_module(&self, module: &Module) -> Result<String, RenderError> {
+        self.render_with(&self.environment()?, module)
+    }
+
+    /// Writes one file per module, e.g. `pkg/module.md`, and returns their
+    /// paths.
+    pub fn render_package(&self, package: &Package, path: &Path) -> Result<Vec<PathBuf>, RenderError> {
+        let environment = self.environment()?;
+        let mut written = Vec::new();
+        for module in package.modules.values() {
+            let text = self.render_with(&environment, module)?;
+            let mut file_path = path.to_path_buf();
+            file_path.extend(module.name.split('.'));
+            file_path.set_extension(&self.extension);
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&file_path, text)?;
+            written.push(file_path);
+        }
+        Ok(written)
+    }
+
+    fn render_with(&self, environment: &Environment<'_>, module: &Module) -> Result<String, RenderError> {
+        let include_private = self.private_modules.get(&module.name).copied().unwrap_or(self.include_private);
+        let node = TemplateNode::from_module(module, include_private);
+        let template = environment.get_template(&format!("module.{}", self.extension))?;
+        let text = template.render(minijinja::context! { node => node })?;
+        Ok(format!("{}\n", text.trim_end()))
+    }
+}
+
+fn members(
+    node: &Rc<RefCell<ContextNode>>,
+    prefix: &str,
+    level: usize,
+    include_private: bool,
+) -> Vec<TemplateNode> {
+    let parent = node.borrow();
+    let mut names = HashSet::new();
+    let mut nodes = Vec::new();
+    for child in parent.children.iter() {
+        let child_node = child.borrow();
+        let kind = match child_node.context_type {
+            ContextType::CLASS => "class",
+            ContextType::METHOD if parent.context_type == ContextType::CLASS => "method",
+            ContextType::METHOD => "function",
+            ContextType::PROPERTY => "property",
+            ContextType::ATTRIBUTE => "attribute",
+            ContextType::VARIABLE | ContextType::ALIAS if parent.context_type == ContextType::CLASS => "attribute",
+            ContextType::VARIABLE | ContextType::ALIAS => "data",
+            _ => continue,
+        };
+        // Conditional definitions only keep their first branch
+        if (!include_private && !child_node.is_public) || !names.insert(child_node.identifier().to_string()) {
+            continue;
+        }
+        let qualname = format!("{}.{}", prefix, child_node.identifier());
+        let docstring = child_node.docstring();
+        let variable = child_node.variable.as_ref();
+        nodes.push(TemplateNode {
+            name: child_node.identifier().to_string(),
+            kind: kind.to_string(),
+            level,
+            signature: child_node.signature.as_ref().map(|s| s.render()),
+            annotation: child_node
+                .property
+                .as_ref()
+                .and_then(|p| p.annotation.clone())
+                .or_else(|| variable.and_then(|v| v.annotation.clone())),
+            value: variable.and_then(|v| v.value.clone()),
+            bases: child_node
+                .class_header
+                .as_ref()
+                .map(|h| h.bases.clone())
+                .unwrap_or_default(),
+            decorators: child_node.decorators.clone(),
+            deprecated: child_node.deprecation.as_ref().map(|d| d.badge()),
+            summary: docstring.as_deref().map(summary),
+            docstring,
+            line: child_node.span.start_line,
+            members: match child_node.context_type {
+                ContextType::CLASS => members(child, &qualname, level + 1, include_private),
+                _ => Vec::new(),
+            },
+            qualname,
+        });
+    }
+    nodes
+}
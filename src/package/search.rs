@@ -0,0 +1,292 @@
+//! Full-text search index of the documented symbols of a package, written as
+//! JSON for client-side search and queried from Rust.
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+
+use super::{is_public_name, Package};
+
+/// Weight of a term found in the name of a symbol
+const NAME_WEIGHT: u32 = 10;
+
+/// Weight of a term found in the qualified name, outside of the name
+const PATH_WEIGHT: u32 = 3;
+
+/// Weight of a term found in the summary line of the docstring
+const SUMMARY_WEIGHT: u32 = 2;
+
+/// Weight of a term found in the rest of the docstring
+const TEXT_WEIGHT: u32 = 1;
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "if", "in", "is", "it", "of",
+    "on", "or", "the", "this", "that", "to", "with",
+];
+
+/// Documented symbol of the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchDocument {
+    /// Qualified name, e.g. `pkg.module.Class.method`
+    pub qualname: String,
+    pub name: String,
+    pub module: String,
+
+    /// `module`, `class`, `function`, `method`, `property`, `attribute` or
+    /// `data`
+    pub kind: String,
+
+    /// First paragraph of the docstring on a single line
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+}
+
+/// Symbol matching a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit<'a> {
+    pub document: &'a SearchDocument,
+    pub score: f64,
+}
+
+impl fmt::Display for SearchHit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.document.qualname, self.document.kind)?;
+        if !self.document.summary.is_empty() {
+            write!(f, ": {}", self.document.summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// Inverted index from stemmed terms to the documents holding them, with
+/// the weight of the term in each document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub documents: Vec<SearchDocument>,
+    pub terms: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+impl SearchIndex {
+    /// Indexes the public modules and symbols of a package.
+    pub fn from_package(package: &Package) -> Self {
+        let mut index = Self::default();
+        for module in package.modules.values() {
+            if !is_public_name(&module.name) {
+                continue;
+            }
+            let root = module.root.borrow();
+            let docstring = root.docstring().unwrap_or_default();
+            index.add(&module.name, &module.name, "module", &docstring);
+            drop(root);
+            collect_documents(&module.root, &module.name, &module.name, &mut index);
+        }
+        index
+    }
+
+    /// Adds a document, indexing its name, qualified name and docstring.
+    pub fn add(&mut self, qualname: &str, module: &str, kind: &str, docstring: &str) {
+        let name = qualname.rsplit('.').next().unwrap_or(qualname);
+        let summary = summary(docstring);
+        let id = self.documents.len();
+        self.documents.push(SearchDocument {
+            qualname: qualname.to_string(),
+            name: name.to_string(),
+            module: module.to_string(),
+            kind: kind.to_string(),
+            summary: summary.clone(),
+        });
+
+        let mut weights: HashMap<String, u32> = HashMap::new();
+        let parts = [
+            (name, NAME_WEIGHT),
+            (qualname, PATH_WEIGHT),
+            (summary.as_str(), SUMMARY_WEIGHT),
+            (docstring, TEXT_WEIGHT),
+        ];
+        for (text, weight) in parts {
+            for term in tokenize(text) {
+                let entry = weights.entry(term).or_insert(0);
+                *entry = (*entry).max(weight);
+            }
+        }
+        for (term, weight) in weights {
+            self.terms.entry(term).or_default().push((id, weight));
+        }
+    }
+
+    /// Returns the documents holding every term of the query, best first.
+    /// Terms are scored by their weight in the document and their rarity in
+    /// the index.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let terms = tokenize(query).into_iter().collect::<HashSet<_>>();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let total = self.documents.len() as f64;
+        let mut scores: HashMap<usize, (usize, f64)> = HashMap::new();
+        for term in terms.iter() {
+            let postings = match self.terms.get(term) {
+                Some(postings) => postings,
+                None => return Vec::new(),
+            };
+            let rarity = (total / postings.len() as f64).ln() + 1.0;
+            for (id, weight) in postings {
+                let score = scores.entry(*id).or_insert((0, 0.0));
+                score.0 += 1;
+                score.1 += *weight as f64 * rarity;
+            }
+        }
+
+        let mut hits = scores
+            .into_iter()
+            .filter(|(_, (matched, _))| *matched == terms.len())
+            .filter_map(|(id, (_, score))| {
+                Some(SearchHit {
+                    document: self.documents.get(id)?,
+                    score,
+                })
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.document.qualname.cmp(&b.document.qualname))
+        });
+        hits
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_json()?)
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn collect_documents(
+    node: &Rc<RefCell<ContextNode>>,
+    module: &str,
+    prefix: &str,
+    index: &mut SearchIndex,
+) {
+    let is_class = node.borrow().context_type == ContextType::CLASS;
+    for child in node.borrow().children.iter() {
+        let child_node = child.borrow();
+        let kind = match child_node.context_type {
+            ContextType::CLASS => "class",
+            ContextType::METHOD if is_class => "method",
+            ContextType::METHOD => "function",
+            ContextType::PROPERTY => "property",
+            ContextType::ATTRIBUTE => "attribute",
+            ContextType::VARIABLE | ContextType::ALIAS if is_class => "attribute",
+            ContextType::VARIABLE | ContextType::ALIAS => "data",
+            _ => continue,
+        };
+        let qualname = format!("{}.{}", prefix, child_node.identifier());
+        if !is_public_name(&qualname) {
+            continue;
+        }
+        let docstring = child_node.docstring().unwrap_or_default();
+        index.add(&qualname, module, kind, &docstring);
+        if child_node.context_type == ContextType::CLASS {
+            collect_documents(child, module, &qualname, index);
+        }
+    }
+}
+
+/// Returns the first paragraph of a cleaned docstring on a single line.
+pub(super) fn summary(docstring: &str) -> String {
+    docstring
+        .lines()
+        .take_while(|l| !l.trim().is_empty())
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits a text into lowercase stemmed terms. Identifiers are split at
+/// underscores, dots and case changes, so that `RetryPolicy` and
+/// `retry_policy` both give `retry` and `policy`.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut start = 0;
+        let chars = word.char_indices().collect::<Vec<_>>();
+        for (position, (offset, c)) in chars.iter().enumerate() {
+            let previous = position.checked_sub(1).map(|p| chars[p].1);
+            let next = chars.get(position + 1).map(|(_, c)| *c);
+            // `HTTPServer` splits before `Server`, `retryPolicy` before `Policy`
+            let boundary = c.is_uppercase()
+                && previous.is_some_and(|p| {
+                    p.is_lowercase() || p.is_numeric() || (p.is_uppercase() && next.is_some_and(char::is_lowercase))
+                });
+            if boundary {
+                terms.push(&word[start..*offset]);
+                start = *offset;
+            }
+        }
+        terms.push(&word[start..]);
+    }
+    terms
+        .into_iter()
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .filter(|t| !STOP_WORDS.contains(&t.as_str()))
+        .map(|t| stem(&t))
+        .collect()
+}
+
+/// Strips the common English inflections, enough for `retries`, `retrying`
+/// and `retried` to match `retry`.
+fn stem(word: &str) -> String {
+    const SUFFIXES: &[(&str, &str)] = &[
+        ("ies", "y"),
+        ("ied", "y"),
+        ("sses", "ss"),
+        ("ing", ""),
+        ("ed", ""),
+        ("es", ""),
+        ("s", ""),
+    ];
+    if !word.chars().all(char::is_alphabetic) {
+        return word.to_string();
+    }
+    let mut stem = word.to_string();
+    for (suffix, replacement) in SUFFIXES {
+        if let Some(base) = word.strip_suffix(suffix) {
+            if base.len() < 3 || (*suffix == "s" && base.ends_with(['s', 'u', 'i'])) {
+                continue;
+            }
+            // `es` only ends plurals after sibilants, `cases` is `case` + `s`
+            if *suffix == "es" && !base.ends_with(['x', 'z']) && !base.ends_with("ch") && !base.ends_with("sh") {
+                continue;
+            }
+            stem = format!("{}{}", base, replacement);
+            break;
+        }
+    }
+    // A final `e` is dropped so that `cache` and `cached` meet at `cach`
+    if stem.len() > 3 && stem.ends_with('e') {
+        stem.pop();
+    }
+    stem
+}
@@ -0,0 +1,418 @@
+//! Generates `.pyi` type stubs from parsed modules.
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+use crate::context_engine::syntax::{
+    AttributeKind, FunctionSignature, ImportStatement, Parameter, Variable,
+};
+
+use super::{Module, Package};
+
+const INDENT: &str = "    ";
+
+/// Writes the public interface of modules as `.pyi` stubs: signatures with
+/// `...` bodies, annotated constants and attributes, imports and `__all__`.
+/// Docstrings and function bodies are left out.
+///
+/// ```
+/// use chapter::package::{Module, StubGenerator};
+///
+/// let module = Module::parse("shapes", "RADIUS = 2.5\n\ndef area(r: float = RADIUS) -> float:\n    return 3.14 * r * r\n");
+/// let stub = StubGenerator::new().generate(&module);
+/// assert_eq!(stub, "RADIUS: float\ndef area(r: float = ...) -> float: ...\n");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StubGenerator {
+    strip_private: bool,
+}
+
+/// Imports the generated code needs on top of the ones of the module.
+#[derive(Default)]
+struct Requirements {
+    overload: bool,
+    incomplete: bool,
+}
+
+impl StubGenerator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leaves out private classes, functions and variables. Dunder names are
+    /// public.
+    pub fn strip_private(mut self, strip_private: bool) -> Self {
+        self.strip_private = strip_private;
+        self
+    }
+
+    /// Generates the stub of a module.
+    pub fn generate(&self, module: &Module) -> String {
+        let root = module.root.borrow();
+        let mut requirements = Requirements::default();
+        let overload = match (module.aliases.get("overload"), module.aliases.get("typing")) {
+            (Some(_), _) => "overload",
+            (None, Some(_)) => "typing.overload",
+            (None, None) => "overload",
+        };
+
+        let mut body: Vec<String> = Vec::new();
+        let mut all = None;
+        let mut names = HashSet::new();
+        let mut previous_class = false;
+        for child in root.children.iter() {
+            let node = child.borrow();
+            if node.context_type == ContextType::ALL {
+                all = node.variable.as_ref().and_then(|v| v.value.clone());
+                continue;
+            }
+            let mut lines = Vec::new();
+            if !self.render(&node, "", overload, &mut names, &mut requirements, &mut lines) {
+                continue;
+            }
+            let is_class = node.context_type == ContextType::CLASS;
+            if !body.is_empty() && (is_class || previous_class) {
+                body.push(String::new());
+            }
+            previous_class = is_class;
+            body.extend(lines);
+        }
+
+        let mut imports: Vec<String> = Vec::new();
+        let mut bound = HashSet::new();
+        for import in module.imports() {
+            if import.names.iter().all(|n| bound.contains(n.bound_name())) {
+                continue;
+            }
+            bound.extend(import.names.iter().map(|n| n.bound_name().to_string()));
+            let line = render_import(&import);
+            if !imports.contains(&line) {
+                imports.push(line);
+            }
+        }
+        if requirements.overload && overload == "overload" && !bound.contains("overload") {
+            imports.push(String::from("from typing import overload"));
+        }
+        if requirements.incomplete {
+            imports.push(String::from("from _typeshed import Incomplete"));
+        }
+
+        let mut sections = Vec::new();
+        if !imports.is_empty() {
+            sections.push(imports.join("\n"));
+        }
+        if let Some(all) = all {
+            sections.push(format!("__all__ = {}", all));
+        }
+        if !body.is_empty() {
+            sections.push(body.join("\n"));
+        }
+        let mut stub = sections.join("\n\n");
+        stub.push('\n');
+        stub
+    }
+
+    /// Renders a node into lines, returning whether it is part of the stub.
+    fn render(
+        &self,
+        node: &ContextNode,
+        indent: &str,
+        overload: &str,
+        names: &mut HashSet<String>,
+        requirements: &mut Requirements,
+        lines: &mut Vec<String>,
+    ) -> bool {
+        let name = node.identifier();
+        if self.strip_private && !node.is_public {
+            return false;
+        }
+        let stubbed = matches!(
+            node.context_type,
+            ContextType::CLASS
+                | ContextType::METHOD
+                | ContextType::PROPERTY
+                | ContextType::VARIABLE
+                | ContextType::ALIAS
+                | ContextType::ATTRIBUTE
+        );
+        // Conditional definitions only keep their first branch
+        if !stubbed || !names.insert(name.to_string()) {
+            return false;
+        }
+
+        match node.context_type {
+            ContextType::CLASS => self.render_class(node, indent, overload, requirements, lines),
+            ContextType::METHOD => render_function(node, indent, overload, requirements, lines),
+            ContextType::PROPERTY => render_property(node, indent, lines),
+            ContextType::ALIAS => {
+                if let Some(variable) = &node.variable {
+                    lines.push(format!("{}{}", indent, render_alias(name, variable)));
+                }
+            }
+            _ => lines.push(format!("{}{}", indent, render_value(node, requirements))),
+        }
+        true
+    }
+
+    fn render_class(
+        &self,
+        node: &ContextNode,
+        indent: &str,
+        overload: &str,
+        requirements: &mut Requirements,
+        lines: &mut Vec<String>,
+    ) {
+        for decorator in node.decorators.iter() {
+            lines.push(format!("{}@{}", indent, decorator));
+        }
+        let mut header = format!("{}class {}", indent, node.identifier());
+        if let Some(class_header) = &node.class_header {
+            if !class_header.type_params.is_empty() {
+                header.push_str(&format!("[{}]", class_header.type_params.join(", ")));
+            }
+            let mut arguments = class_header.bases.clone();
+            if let Some(metaclass) = &class_header.metaclass {
+                arguments.push(format!("metaclass={}", metaclass));
+            }
+            for (keyword, value) in class_header.keywords.iter() {
+                arguments.push(format!("{}={}", keyword, value));
+            }
+            if !arguments.is_empty() {
+                header.push_str(&format!("({})", arguments.join(", ")));
+            }
+        }
+        header.push(':');
+
+        let members = node
+            .enum_members
+            .as_ref()
+            .map(|members| members.iter().map(|m| m.name.as_str()).collect::<HashSet<_>>())
+            .unwrap_or_default();
+        let body_indent = format!("{}{}", indent, INDENT);
+        let mut body = Vec::new();
+        let mut names = HashSet::new();
+        for child in node.children.iter() {
+            let child = child.borrow();
+            if members.contains(child.identifier()) {
+                if let Some(value) = child.variable.as_ref().and_then(|v| v.value.as_ref()) {
+                    names.insert(child.identifier().to_string());
+                    body.push(format!("{}{} = {}", body_indent, child.identifier(), value));
+                }
+                continue;
+            }
+            self.render(&child, &body_indent, overload, &mut names, requirements, &mut body);
+        }
+
+        if body.is_empty() {
+            lines.push(format!("{} ...", header));
+        } else {
+            lines.push(header);
+            lines.extend(body);
+        }
+    }
+}
+
+impl Package {
+    /// Writes the stub of every module below a directory, following the
+    /// layout of the package: `a.b` goes to `a/b.pyi` and the package `a` to
+    /// `a/__init__.pyi`. Returns the paths of the written files.
+    pub fn write_stubs(&self, path: &Path, generator: &StubGenerator) -> io::Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for module in self.modules.values() {
+            let mut file = path.to_path_buf();
+            file.extend(module.name.split('.').filter(|part| !part.is_empty()));
+            if module.is_package {
+                file.push("__init__.pyi");
+            } else {
+                file.set_extension("pyi");
+            }
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&file, generator.generate(module))?;
+            written.push(file);
+        }
+        Ok(written)
+    }
+}
+
+fn render_import(import: &ImportStatement) -> String {
+    let names = import
+        .names
+        .iter()
+        .map(|n| match &n.alias {
+            Some(alias) => format!("{} as {}", n.name, alias),
+            None => n.name.clone(),
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+    match import.is_from {
+        true => format!("from {}{} import {}", ".".repeat(import.level), import.module, names),
+        false => format!("import {}", names),
+    }
+}
+
+fn render_alias(name: &str, variable: &Variable) -> String {
+    let value = variable.value.as_deref().unwrap_or("...");
+    if variable.type_statement {
+        return match variable.type_params.is_empty() {
+            true => format!("type {} = {}", name, value),
+            false => format!("type {}[{}] = {}", name, variable.type_params.join(", "), value),
+        };
+    }
+    match &variable.annotation {
+        Some(annotation) => format!("{}: {} = {}", name, annotation, value),
+        None => format!("{} = {}", name, value),
+    }
+}
+
+/// Renders variables and attributes as annotated declarations, inferring the
+/// type of literal values.
+fn render_value(node: &ContextNode, requirements: &mut Requirements) -> String {
+    let name = node.identifier();
+    let variable = node.variable.clone().unwrap_or_default();
+    if variable.is_type_variable() {
+        return format!("{} = {}", name, variable.value.unwrap_or_default());
+    }
+    let has_default = node.attribute.as_ref().is_some_and(|a| {
+        a.kind == AttributeKind::Field && (a.default.is_some() || a.default_factory.is_some())
+    });
+
+    let annotation = match (&variable.annotation, &variable.value) {
+        (Some(annotation), Some(value)) if annotation.rsplit('.').next() == Some("Final") => {
+            return format!("{}: {} = {}", name, annotation, value);
+        }
+        (Some(annotation), _) => annotation.clone(),
+        (None, Some(value)) => match literal_type(value) {
+            Some(literal) => literal.to_string(),
+            None => {
+                requirements.incomplete = true;
+                String::from("Incomplete")
+            }
+        },
+        (None, None) => {
+            requirements.incomplete = true;
+            String::from("Incomplete")
+        }
+    };
+    match has_default {
+        true => format!("{}: {} = ...", name, annotation),
+        false => format!("{}: {}", name, annotation),
+    }
+}
+
+/// Type of a literal expression. `None` says nothing about the type of the
+/// values assigned later, so it gives no type.
+fn literal_type(value: &str) -> Option<&'static str> {
+    let value = value.trim();
+    let unsigned = value.trim_start_matches('-').replace('_', "").to_ascii_lowercase();
+    let radix = [("0x", 16), ("0o", 8), ("0b", 2)]
+        .into_iter()
+        .find_map(|(prefix, radix)| Some((unsigned.strip_prefix(prefix)?, radix)));
+    // Only digits, `.` and exponents, so that names such as `inf` stay untyped
+    let is_float = unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && unsigned.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | '+' | '-'))
+        && unsigned.parse::<f64>().is_ok();
+    let prefix = value
+        .find(['"', '\''])
+        .map(|index| value[..index].to_ascii_lowercase());
+    match value {
+        "True" | "False" => Some("bool"),
+        "None" => None,
+        _ if !unsigned.is_empty() && unsigned.chars().all(|c| c.is_ascii_digit()) => Some("int"),
+        _ if radix.is_some_and(|(digits, radix)| !digits.is_empty() && digits.chars().all(|c| c.is_digit(radix))) => {
+            Some("int")
+        }
+        _ if is_float => Some("float"),
+        _ => match prefix.as_deref() {
+            Some("" | "r" | "u" | "f" | "rf" | "fr") => Some("str"),
+            Some("b" | "rb" | "br") => Some("bytes"),
+            _ => None,
+        },
+    }
+}
+
+/// Renders a signature with the default values replaced by `...`.
+fn stub_signature(signature: &FunctionSignature) -> String {
+    let signature = FunctionSignature {
+        parameters: signature
+            .parameters
+            .iter()
+            .map(|p| Parameter {
+                default: p.default.as_ref().map(|_| String::from("...")),
+                ..p.clone()
+            })
+            .collect(),
+        ..signature.clone()
+    };
+    signature.render()
+}
+
+fn render_function(
+    node: &ContextNode,
+    indent: &str,
+    overload: &str,
+    requirements: &mut Requirements,
+    lines: &mut Vec<String>,
+) {
+    let name = node.identifier();
+    let definition = |signature: &FunctionSignature| {
+        let keyword = if signature.is_async { "async def" } else { "def" };
+        format!("{}{} {}{}: ...", indent, keyword, name, stub_signature(signature))
+    };
+
+    if !node.overloads.is_empty() {
+        requirements.overload = true;
+        let decorators = node
+            .decorators
+            .iter()
+            .filter(|d| d.rsplit('.').next() != Some("overload"));
+        for signature in node.overloads.iter() {
+            for decorator in decorators.clone() {
+                lines.push(format!("{}@{}", indent, decorator));
+            }
+            lines.push(format!("{}@{}", indent, overload));
+            lines.push(definition(signature));
+        }
+        return;
+    }
+
+    for decorator in node.decorators.iter() {
+        lines.push(format!("{}@{}", indent, decorator));
+    }
+    match &node.signature {
+        Some(signature) => lines.push(definition(signature)),
+        None => lines.push(format!("{}def {}(*args, **kwargs): ...", indent, name)),
+    }
+}
+
+fn render_property(node: &ContextNode, indent: &str, lines: &mut Vec<String>) {
+    let name = node.identifier();
+    for decorator in node.decorators.iter() {
+        lines.push(format!("{}@{}", indent, decorator));
+    }
+    let signature = node
+        .signature
+        .as_ref()
+        .map(stub_signature)
+        .unwrap_or_else(|| String::from("(self)"));
+    lines.push(format!("{}def {}{}: ...", indent, name, signature));
+
+    let property = match &node.property {
+        Some(property) => property,
+        None => return,
+    };
+    if property.writable {
+        let value = match &property.annotation {
+            Some(annotation) => format!("value: {}", annotation),
+            None => String::from("value"),
+        };
+        lines.push(format!("{}@{}.setter", indent, name));
+        lines.push(format!("{}def {}(self, {}) -> None: ...", indent, name, value));
+    }
+    if property.deletable {
+        lines.push(format!("{}@{}.deleter", indent, name));
+        lines.push(format!("{}def {}(self) -> None: ...", indent, name));
+    }
+}
@@ -0,0 +1,183 @@
+//! Merges `.pyi` stub files into the trees of the modules they describe.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::context_engine::definitions::{ContextNode, ContextType};
+
+use super::{Module, Package};
+
+/// Symbols declared by only one of a module and its stub, as dotted paths
+/// inside the module such as `Client.connect`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StubReport {
+    pub module: String,
+
+    /// Public symbols of the implementation the stub does not declare
+    pub missing_in_stub: Vec<String>,
+
+    /// Symbols of the stub the implementation does not define
+    pub missing_in_implementation: Vec<String>,
+}
+
+impl StubReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing_in_stub.is_empty() && self.missing_in_implementation.is_empty()
+    }
+}
+
+impl Module {
+    /// Parses the source of a `.pyi` stub.
+    pub fn parse_stub(name: &str, source: &str) -> Self {
+        let mut module = Self::parse(name, source);
+        module.is_stub = true;
+        module
+    }
+
+    /// Merges a stub into the module: signatures, class headers and
+    /// annotations come from the stub while docstrings stay the ones of the
+    /// implementation unless it has none. The nodes of symbols only the stub
+    /// declares are moved to the tree of the module.
+    pub fn merge_stub(&mut self, stub: &Module) -> StubReport {
+        let mut report = StubReport {
+            module: self.name.clone(),
+            ..StubReport::default()
+        };
+        merge_children(&self.root, &stub.root, "", &mut report);
+        report
+    }
+}
+
+impl Package {
+    /// Adds a parsed stub, merged into its module when the package holds the
+    /// implementation and added on its own otherwise.
+    pub fn add_stub(&mut self, stub: Module) {
+        match self.modules.get_mut(&stub.name) {
+            Some(module) if !module.is_stub => {
+                let report = module.merge_stub(&stub);
+                self.stub_reports.push(report);
+            }
+            _ => self.add_module(stub),
+        }
+    }
+
+    /// Adds a parsed implementation, merging the stub read before it.
+    pub fn add_implementation(&mut self, mut module: Module) {
+        match self.modules.remove(&module.name) {
+            Some(stub) if stub.is_stub => {
+                let report = module.merge_stub(&stub);
+                self.stub_reports.push(report);
+                self.add_module(module);
+            }
+            _ => self.add_module(module),
+        }
+    }
+}
+
+/// Kinds of symbols stubs and implementations are compared on.
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Symbol {
+    Class,
+    Function,
+    Value,
+}
+
+fn symbol_group(context_type: &ContextType) -> Option<Symbol> {
+    match context_type {
+        ContextType::CLASS => Some(Symbol::Class),
+        ContextType::METHOD | ContextType::PROPERTY => Some(Symbol::Function),
+        ContextType::VARIABLE | ContextType::ALIAS | ContextType::ATTRIBUTE => Some(Symbol::Value),
+        _ => None,
+    }
+}
+
+fn merge_children(
+    node: &Rc<RefCell<ContextNode>>,
+    stub: &Rc<RefCell<ContextNode>>,
+    path: &str,
+    report: &mut StubReport,
+) {
+    let qualified = |name: &str| match path.is_empty() {
+        true => name.to_string(),
+        false => format!("{}.{}", path, name),
+    };
+    let symbols = |node: &Rc<RefCell<ContextNode>>| {
+        node.borrow()
+            .children
+            .iter()
+            .filter(|c| symbol_group(&c.borrow().context_type).is_some())
+            .map(Rc::clone)
+            .collect::<Vec<_>>()
+    };
+    let stub_symbols = symbols(stub);
+    let mut matched = vec![false; stub_symbols.len()];
+
+    for child in symbols(node) {
+        let (name, group, is_public) = {
+            let child = child.borrow();
+            (
+                child.identifier().to_string(),
+                symbol_group(&child.context_type),
+                child.is_public,
+            )
+        };
+        let counterpart = stub_symbols.iter().enumerate().find(|(index, s)| {
+            let s = s.borrow();
+            !matched[*index] && s.identifier() == name && symbol_group(&s.context_type) == group
+        });
+        match counterpart {
+            Some((index, counterpart)) => {
+                matched[index] = true;
+                merge_node(&child, counterpart);
+                if group == Some(Symbol::Class) {
+                    merge_children(&child, counterpart, &qualified(&name), report);
+                }
+            }
+            None if is_public => report.missing_in_stub.push(qualified(&name)),
+            None => (),
+        }
+    }
+
+    for (symbol, _) in stub_symbols.iter().zip(matched).filter(|(_, m)| !m) {
+        report
+            .missing_in_implementation
+            .push(qualified(symbol.borrow().identifier()));
+        symbol.borrow_mut().set_parent(Rc::clone(node));
+        node.borrow_mut().children.push(Rc::clone(symbol));
+    }
+}
+
+/// Copies the typing information of a stub node onto the implementation.
+fn merge_node(node_rc: &Rc<RefCell<ContextNode>>, stub: &Rc<RefCell<ContextNode>>) {
+    let stub = stub.borrow();
+    let mut node = node_rc.borrow_mut();
+
+    if stub.class_header.is_some() {
+        node.class_header = stub.class_header.clone();
+    }
+    if stub.signature.is_some() {
+        node.signature = stub.signature.clone();
+    }
+    if !stub.overloads.is_empty() {
+        node.overloads = stub.overloads.clone();
+    }
+    if let Some(stub_property) = &stub.property {
+        let property = node.property.get_or_insert_with(|| stub_property.clone());
+        property.annotation = stub_property.annotation.clone();
+    }
+    let annotation = stub.variable.as_ref().and_then(|v| v.annotation.clone());
+    if let (Some(variable), Some(annotation)) = (node.variable.as_mut(), annotation) {
+        variable.annotation = Some(annotation);
+    }
+
+    if node.docstring().is_none() {
+        let docstring = stub
+            .children
+            .iter()
+            .find(|c| c.borrow().context_type == ContextType::DOCSTRING)
+            .map(Rc::clone);
+        if let Some(docstring) = docstring {
+            docstring.borrow_mut().set_parent(Rc::clone(node_rc));
+            node.children.insert(0, docstring);
+        }
+    }
+}